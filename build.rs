@@ -0,0 +1,157 @@
+// Every image the story references is resized so its longest side fits the
+// story's "max_texture_size", converted to png in OUT_DIR and baked into the
+// binary, so neither build needs the photos next to it. A referenced file that
+// does not exist fails the build.
+//
+// With the `embed-assets` feature the story and fonts are baked in too, so the
+// desktop build runs from any directory and the wasm needs nothing next to it.
+
+use std::path::Path;
+
+#[allow(dead_code)]
+mod asset_paths {
+    include!("src/asset_paths.rs");
+}
+
+use asset_paths::{DEFAULT_MAX_TEXTURE_SIZE, FONT_PATHS, HEART_PATH, STAR_PATH, STORY_PATH};
+
+// holds the size limit the images in OUT_DIR were resized to
+const STAMP_PATH: &str = "max_texture_size";
+
+struct ReferencedAssets {
+    fonts: Vec<String>,
+    images: Vec<String>,
+    max_texture_size: u32,
+}
+
+fn read_story(root: &Path) -> ReferencedAssets {
+    let json = std::fs::read_to_string(root.join(STORY_PATH))
+        .unwrap_or_else(|error| panic!("{STORY_PATH}: {error}"));
+    let story: serde_json::Value =
+        serde_json::from_str(&json).unwrap_or_else(|error| panic!("{STORY_PATH}: {error}"));
+
+    let mut images = vec![HEART_PATH.to_string(), STAR_PATH.to_string()];
+    for (index, scene) in story["scenes"].as_array().into_iter().flatten().enumerate() {
+        let Some(texture) = scene["texture"].as_str() else {
+            panic!("{STORY_PATH}: scene {index} has no texture");
+        };
+        if !images.iter().any(|path| path == texture) {
+            images.push(texture.to_string());
+        }
+    }
+
+    ReferencedAssets {
+        fonts: FONT_PATHS.iter().map(|path| path.to_string()).collect(),
+        images,
+        max_texture_size: story["max_texture_size"]
+            .as_u64()
+            .map_or(DEFAULT_MAX_TEXTURE_SIZE, |size| size as u32),
+    }
+}
+
+fn check_exists(root: &Path, path: &str) {
+    if !root.join(path).exists() {
+        panic!("{path} is referenced but does not exist");
+    }
+    println!("cargo:rerun-if-changed={path}");
+}
+
+// "images/us.jpg" becomes "images/us.jpg.png", keeping the extension so
+// us.jpg and us.png do not end up in the same file
+fn processed_image_path(source: &str) -> String {
+    format!("{source}.png")
+}
+
+fn is_newer(output: &Path, input: &Path) -> bool {
+    let modified = |path: &Path| {
+        path.metadata()
+            .and_then(|metadata| metadata.modified())
+            .ok()
+    };
+    match (modified(output), modified(input)) {
+        (Some(output_time), Some(input_time)) => input_time <= output_time,
+        _ => false,
+    }
+}
+
+// whether the images already in OUT_DIR were resized to this limit
+fn same_size_limit(out_dir: &Path, max_texture_size: u32) -> bool {
+    std::fs::read_to_string(out_dir.join(STAMP_PATH))
+        .is_ok_and(|stamp| stamp.trim() == max_texture_size.to_string())
+}
+
+fn process_image(root: &Path, out_dir: &Path, source: &str, max_texture_size: u32, reuse: bool) {
+    let source_path = root.join(source);
+    let output_path = out_dir.join(processed_image_path(source));
+    if reuse && is_newer(&output_path, &source_path) {
+        return;
+    }
+
+    let image = image::open(&source_path).unwrap_or_else(|error| panic!("{source}: {error}"));
+    let image = if image.width().max(image.height()) > max_texture_size {
+        image.resize(
+            max_texture_size,
+            max_texture_size,
+            image::imageops::FilterType::Lanczos3,
+        )
+    } else {
+        image
+    };
+
+    std::fs::create_dir_all(output_path.parent().unwrap()).unwrap();
+    image
+        .to_rgba8()
+        .save_with_format(&output_path, image::ImageFormat::Png)
+        .unwrap_or_else(|error| panic!("{}: {error}", output_path.display()));
+}
+
+// PROCESSED_IMAGES: &[(&str, &[u8])], the png for each image path in the story
+fn write_processed_images(out_dir: &Path, assets: &ReferencedAssets) {
+    let mut code = String::from("pub static PROCESSED_IMAGES: &[(&str, &[u8])] = &[\n");
+    for path in &assets.images {
+        let output_path = out_dir.join(processed_image_path(path));
+        code += &format!("    ({path:?}, include_bytes!({output_path:?})),\n");
+    }
+    code += "];\n";
+    std::fs::write(out_dir.join("processed_images.rs"), code).unwrap();
+}
+
+#[cfg(feature = "embed-assets")]
+fn write_embedded_assets(root: &Path, out_dir: &Path, assets: &ReferencedAssets) {
+    let mut paths = vec![STORY_PATH.to_string()];
+    paths.extend(assets.fonts.iter().cloned());
+
+    let mut code = String::from("pub static EMBEDDED_ASSETS: &[(&str, &[u8])] = &[\n");
+    for path in paths {
+        code += &format!("    ({path:?}, include_bytes!({:?})),\n", root.join(&path));
+    }
+    code += "];\n";
+    std::fs::write(out_dir.join("embedded_assets.rs"), code).unwrap();
+}
+
+fn main() {
+    let root = std::env::var("CARGO_MANIFEST_DIR").unwrap();
+    let root = Path::new(&root);
+    let out_dir = std::env::var("OUT_DIR").unwrap();
+    let out_dir = Path::new(&out_dir);
+    println!("cargo:rerun-if-changed={STORY_PATH}");
+    println!("cargo:rerun-if-changed=src/asset_paths.rs");
+
+    let assets = read_story(root);
+    for path in assets.fonts.iter().chain(&assets.images) {
+        check_exists(root, path);
+    }
+    let reuse = same_size_limit(out_dir, assets.max_texture_size);
+    for path in &assets.images {
+        process_image(root, out_dir, path, assets.max_texture_size, reuse);
+    }
+    std::fs::write(
+        out_dir.join(STAMP_PATH),
+        assets.max_texture_size.to_string(),
+    )
+    .unwrap();
+    write_processed_images(out_dir, &assets);
+
+    #[cfg(feature = "embed-assets")]
+    write_embedded_assets(root, out_dir, &assets);
+}
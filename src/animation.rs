@@ -0,0 +1,306 @@
+use macroquad::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::story::{color_format, is_default};
+
+#[derive(Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Easing {
+    #[default]
+    Linear,
+    EaseIn,
+    EaseOut,
+    EaseInOut,
+    // overshoots the target a little and settles back
+    Back,
+    Bounce,
+}
+
+impl Easing {
+    pub fn apply(self, t: f32) -> f32 {
+        let t = t.clamp(0.0, 1.0);
+        match self {
+            Easing::Linear => t,
+            Easing::EaseIn => t * t * t,
+            Easing::EaseOut => 1.0 - (1.0 - t).powi(3),
+            Easing::EaseInOut => {
+                if t < 0.5 {
+                    4.0 * t * t * t
+                } else {
+                    1.0 - (-2.0 * t + 2.0).powi(3) / 2.0
+                }
+            }
+            Easing::Back => {
+                let overshoot = 1.70158;
+                1.0 + (overshoot + 1.0) * (t - 1.0).powi(3) + overshoot * (t - 1.0).powi(2)
+            }
+            Easing::Bounce => {
+                let (n, d) = (7.5625, 2.75);
+                if t < 1.0 / d {
+                    n * t * t
+                } else if t < 2.0 / d {
+                    let t = t - 1.5 / d;
+                    n * t * t + 0.75
+                } else if t < 2.5 / d {
+                    let t = t - 2.25 / d;
+                    n * t * t + 0.9375
+                } else {
+                    let t = t - 2.625 / d;
+                    n * t * t + 0.984375
+                }
+            }
+        }
+    }
+}
+
+// A moment of an animation. Properties left out follow the keyframes around it.
+#[derive(Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Keyframe {
+    // seconds since the scene was entered
+    pub time: f32,
+    // curve used on the way from the previous keyframe to this one
+    #[serde(skip_serializing_if = "is_default")]
+    pub easing: Easing,
+    // in pixels, relative to where the element is normally drawn
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub offset: Option<(f32, f32)>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub scale: Option<f32>,
+    // in degrees, clockwise
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub rotation: Option<f32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub opacity: Option<f32>,
+    // multiplies the element's own colors
+    #[serde(with = "color_format::option", skip_serializing_if = "Option::is_none")]
+    pub color: Option<Color>,
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+pub struct Animation {
+    // in time order
+    pub keyframes: Vec<Keyframe>,
+    // starts over after the last keyframe instead of holding it
+    #[serde(default, skip_serializing_if = "is_default")]
+    pub looped: bool,
+}
+
+// what each part of a scene does after the scene is entered
+#[derive(Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SceneAnimations {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub texture: Option<Animation>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub text: Option<Animation>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub background: Option<Animation>,
+}
+
+impl SceneAnimations {
+    pub fn is_empty(&self) -> bool {
+        self.texture.is_none() && self.text.is_none() && self.background.is_none()
+    }
+}
+
+#[derive(Clone, Copy)]
+pub struct Pose {
+    pub offset: Vec2,
+    pub scale: f32,
+    // in radians
+    pub rotation: f32,
+    pub opacity: f32,
+    pub color: Color,
+}
+
+impl Default for Pose {
+    fn default() -> Self {
+        Pose {
+            offset: Vec2::ZERO,
+            scale: 1.0,
+            rotation: 0.0,
+            opacity: 1.0,
+            color: WHITE,
+        }
+    }
+}
+
+fn lerp_color(from: Color, to: Color, t: f32) -> Color {
+    Color::new(
+        from.r + (to.r - from.r) * t,
+        from.g + (to.g - from.g) * t,
+        from.b + (to.b - from.b) * t,
+        from.a + (to.a - from.a) * t,
+    )
+}
+
+// the value of one property at the given time, None when no keyframe sets it
+fn track<T: Copy>(
+    keyframes: &[Keyframe],
+    time: f32,
+    value: impl Fn(&Keyframe) -> Option<T>,
+    lerp: impl Fn(T, T, f32) -> T,
+) -> Option<T> {
+    let mut previous: Option<(f32, T)> = None;
+    for keyframe in keyframes {
+        let Some(next) = value(keyframe) else {
+            continue;
+        };
+        if keyframe.time >= time {
+            return Some(match previous {
+                None => next,
+                Some((previous_time, previous_value)) => {
+                    let span = keyframe.time - previous_time;
+                    let t = if span > 0.0 {
+                        (time - previous_time) / span
+                    } else {
+                        1.0
+                    };
+                    lerp(previous_value, next, keyframe.easing.apply(t))
+                }
+            });
+        }
+        previous = Some((keyframe.time, next));
+    }
+    previous.map(|(_, value)| value)
+}
+
+impl Animation {
+    fn duration(&self) -> f32 {
+        self.keyframes
+            .iter()
+            .map(|keyframe| keyframe.time)
+            .fold(0.0, f32::max)
+    }
+
+    pub fn sample(&self, elapsed: f32) -> Pose {
+        let duration = self.duration();
+        let time = if self.looped && duration > 0.0 {
+            elapsed % duration
+        } else {
+            elapsed
+        };
+        let keyframes = &self.keyframes;
+        let lerp = |from: f32, to: f32, t: f32| from + (to - from) * t;
+        let identity = Pose::default();
+
+        Pose {
+            offset: track(
+                keyframes,
+                time,
+                |keyframe| keyframe.offset.map(|(x, y)| vec2(x, y)),
+                |from, to, t| from.lerp(to, t),
+            )
+            .unwrap_or(identity.offset),
+            scale: track(keyframes, time, |keyframe| keyframe.scale, lerp)
+                .unwrap_or(identity.scale),
+            rotation: track(keyframes, time, |keyframe| keyframe.rotation, lerp)
+                .map_or(identity.rotation, f32::to_radians),
+            opacity: track(keyframes, time, |keyframe| keyframe.opacity, lerp)
+                .unwrap_or(identity.opacity),
+            color: track(keyframes, time, |keyframe| keyframe.color, lerp_color)
+                .unwrap_or(identity.color),
+        }
+    }
+}
+
+pub fn sample(animation: Option<&Animation>, elapsed: f32) -> Pose {
+    animation.map_or_else(Pose::default, |animation| animation.sample(elapsed))
+}
+
+pub fn tint(color: Color, tint: Color) -> Color {
+    Color::new(
+        color.r * tint.r,
+        color.g * tint.g,
+        color.b * tint.b,
+        color.a * tint.a,
+    )
+}
+
+impl Pose {
+    // the color to multiply drawn colors by, with the opacity folded in
+    pub fn tint(&self) -> Color {
+        Color {
+            a: self.color.a * self.opacity.clamp(0.0, 1.0),
+            ..self.color
+        }
+    }
+
+    // draws with the pose applied around the pivot, in screen pixels
+    pub fn draw_around(&self, pivot: Vec2, draw: impl FnOnce()) {
+        let transform = Mat4::from_translation((pivot + self.offset).extend(0.0))
+            * Mat4::from_rotation_z(self.rotation)
+            * Mat4::from_scale(vec3(self.scale, self.scale, 1.0))
+            * Mat4::from_translation((-pivot).extend(0.0));
+        unsafe { get_internal_gl() }
+            .quad_gl
+            .push_model_matrix(transform);
+        draw();
+        unsafe { get_internal_gl() }.quad_gl.pop_model_matrix();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn animation(json: &str) -> Animation {
+        serde_json::from_str(json).unwrap()
+    }
+
+    fn close(a: f32, b: f32) -> bool {
+        (a - b).abs() < 1e-4
+    }
+
+    #[test]
+    fn properties_left_out_follow_the_keyframes_around_them() {
+        let fade = animation(
+            r#"{"keyframes": [
+                {"time": 0, "opacity": 0, "scale": 2},
+                {"time": 1, "offset": [100, 0]},
+                {"time": 2, "opacity": 1}
+            ]}"#,
+        );
+        let pose = fade.sample(1.0);
+        assert!(close(pose.opacity, 0.5));
+        assert_eq!(pose.scale, 2.0);
+        assert_eq!(pose.offset, vec2(100.0, 0.0));
+        assert_eq!(pose.rotation, 0.0);
+        assert_eq!(pose.color, WHITE);
+
+        // before the first keyframe of a property, and after its last
+        assert_eq!(fade.sample(0.0).offset, vec2(100.0, 0.0));
+        assert_eq!(fade.sample(5.0).opacity, 1.0);
+        assert!(close(fade.sample(0.5).offset.x, 100.0));
+    }
+
+    #[test]
+    fn zero_length_spans_jump() {
+        let jump = animation(
+            r#"{"keyframes": [
+                {"time": 1, "rotation": 0},
+                {"time": 1, "rotation": 90},
+                {"time": 2, "rotation": 180, "easing": "ease_in"}
+            ]}"#,
+        );
+        assert!(close(jump.sample(1.0).rotation, 0.0));
+        assert!(close(jump.sample(1.001).rotation, 90f32.to_radians()));
+        // a cubic ease in is an eighth of the way at half the time
+        assert!(close(jump.sample(1.5).rotation, 101.25f32.to_radians()));
+    }
+
+    #[test]
+    fn looped_animations_start_over() {
+        let json = r#"{"keyframes": [{"time": 0, "scale": 1}, {"time": 2, "scale": 3}]"#;
+        let once = animation(&format!("{json}}}"));
+        let looped = animation(&format!(r#"{json}, "looped": true}}"#));
+        assert!(close(looped.sample(1.0).scale, 2.0));
+        assert!(close(looped.sample(5.0).scale, 2.0));
+        assert!(close(once.sample(5.0).scale, 3.0));
+
+        let still = animation(r#"{"keyframes": [{"time": 0, "opacity": 0.5}], "looped": true}"#);
+        assert_eq!(still.sample(3.0).opacity, 0.5);
+        assert_eq!(sample(None, 3.0).scale, 1.0);
+    }
+}
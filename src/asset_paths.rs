@@ -0,0 +1,16 @@
+// Files the game loads besides the scene textures listed in the story.
+// build.rs includes this file as is, so it must not depend on the rest of the crate.
+
+pub const STORY_PATH: &str = "story.json";
+
+pub const FONT_PATHS: [&str; 3] = [
+    "fonts/Swampy Clean.ttf",
+    "fonts/MorfinSans-Regular.ttf",
+    "fonts/Ramona-Bold.ttf",
+];
+
+pub const HEART_PATH: &str = "images/heart.png";
+pub const STAR_PATH: &str = "images/star.png";
+
+// longest side of an image build.rs resized unless the story sets "max_texture_size"
+pub const DEFAULT_MAX_TEXTURE_SIZE: u32 = 1024;
@@ -0,0 +1,442 @@
+use macroquad::{experimental::coroutines::*, prelude::*};
+
+use crate::asset_paths::{FONT_PATHS, HEART_PATH, STAR_PATH};
+use crate::debug::texture_bytes;
+use crate::fonts::{font_from_bytes, LoadedFont};
+use crate::particles::SpriteBatch;
+use crate::share::decode_story;
+use crate::story::Story;
+
+#[cfg(feature = "embed-assets")]
+mod embedded {
+    // EMBEDDED_ASSETS: &[(&str, &[u8])], generated by build.rs
+    include!(concat!(env!("OUT_DIR"), "/embedded_assets.rs"));
+}
+mod processed {
+    // PROCESSED_IMAGES: &[(&str, &[u8])], the images build.rs resized, as png
+    include!(concat!(env!("OUT_DIR"), "/processed_images.rs"));
+}
+const PROGRESS_HEARTS: usize = 10;
+const PLACEHOLDER_SIZE: u16 = 256;
+// scene textures kept in memory around the current scene
+const PREFETCH_AHEAD: usize = 2;
+const KEEP_BEHIND: usize = 1;
+// longest side of the scene previews in the overview
+const THUMBNAIL_SIZE: u16 = 160;
+
+pub struct Assets {
+    pub story: Story,
+    pub fonts: Vec<LoadedFont>,
+    pub heart_texture: Texture2D,
+    pub star_texture: Texture2D,
+    // the heart and the star again, packed for drawing particles
+    pub particles: SpriteBatch,
+    pub scene_textures: SceneTextures,
+    // files that failed to load and were replaced with placeholders
+    pub warnings: Vec<String>,
+}
+
+pub enum StorySource {
+    File(String),
+    // the payload of a story link
+    Link(String),
+}
+
+enum AssetKind {
+    Story,
+    Font(usize),
+    Heart,
+    Star,
+}
+
+struct Request {
+    kind: AssetKind,
+    path: String,
+    file: Coroutine<Result<Vec<u8>, String>>,
+}
+
+// the copy built into the binary with the `embed-assets` feature
+pub fn embedded_file(path: &str) -> Option<&'static [u8]> {
+    #[cfg(feature = "embed-assets")]
+    return embedded::EMBEDDED_ASSETS
+        .iter()
+        .find(|(embedded_path, _)| *embedded_path == path)
+        .map(|(_, bytes)| *bytes);
+    #[cfg(not(feature = "embed-assets"))]
+    {
+        let _ = path;
+        None
+    }
+}
+
+// the copy built into the binary when there is one, the file next to it otherwise
+pub async fn read_file(path: &str) -> Result<Vec<u8>, String> {
+    match embedded_file(path) {
+        Some(bytes) => Ok(bytes.to_vec()),
+        None => load_file(path).await.map_err(|error| error.to_string()),
+    }
+}
+
+fn start_fetch(path: &str) -> Coroutine<Result<Vec<u8>, String>> {
+    let path = path.to_string();
+    start_coroutine(async move { read_file(&path).await })
+}
+
+// the resized copy built in, the original for images added to the story after the build
+fn start_image_fetch(source: &str) -> Coroutine<Result<Vec<u8>, String>> {
+    let source = source.to_string();
+    start_coroutine(async move {
+        match processed::PROCESSED_IMAGES
+            .iter()
+            .find(|(processed_path, _)| *processed_path == source)
+        {
+            Some((_, bytes)) => Ok(bytes.to_vec()),
+            None => read_file(&source).await,
+        }
+    })
+}
+
+fn start_request(kind: AssetKind, path: &str) -> Request {
+    Request {
+        kind,
+        path: path.to_string(),
+        file: start_fetch(path),
+    }
+}
+
+fn take_fetched(file: &Coroutine<Result<Vec<u8>, String>>) -> Result<Vec<u8>, String> {
+    file.retrieve()
+        .unwrap_or_else(|| Err("loading was interrupted".to_string()))
+}
+
+fn placeholder_image() -> Image {
+    let mut image = Image::gen_image_color(PLACEHOLDER_SIZE, PLACEHOLDER_SIZE, WHITE);
+    let cell = PLACEHOLDER_SIZE as u32 / 8;
+    for x in 0..PLACEHOLDER_SIZE as u32 {
+        for y in 0..PLACEHOLDER_SIZE as u32 {
+            if (x / cell + y / cell).is_multiple_of(2) {
+                image.set_pixel(x, y, PINK);
+            }
+        }
+    }
+    image
+}
+
+fn decode_image(bytes: &[u8]) -> Result<Image, String> {
+    Image::from_file_with_format(bytes, None).map_err(|error| error.to_string())
+}
+
+fn image_or_placeholder(
+    bytes: Result<Vec<u8>, String>,
+    path: &str,
+    warnings: &mut Vec<String>,
+) -> Image {
+    match bytes.and_then(|bytes| decode_image(&bytes)) {
+        Ok(image) => image,
+        Err(error) => {
+            warnings.push(format!("{}: {}", path, error));
+            placeholder_image()
+        }
+    }
+}
+
+// averages blocks of pixels, good enough for small previews
+fn shrink_image(image: &Image, max_size: u16) -> Image {
+    let scale = (max_size as f32 / image.width.max(image.height) as f32).min(1.0);
+    let width = ((image.width as f32 * scale) as u16).max(1);
+    let height = ((image.height as f32 * scale) as u16).max(1);
+    let mut sums = vec![[0u32; 5]; width as usize * height as usize];
+    for (index, pixel) in image.get_image_data().iter().enumerate() {
+        let x = (index % image.width as usize) * width as usize / image.width as usize;
+        let y = (index / image.width as usize) * height as usize / image.height as usize;
+        let sum = &mut sums[y * width as usize + x];
+        for channel in 0..4 {
+            sum[channel] += pixel[channel] as u32;
+        }
+        sum[4] += 1;
+    }
+
+    let mut small = Image::gen_image_color(width, height, BLANK);
+    for (pixel, sum) in small.get_image_data_mut().iter_mut().zip(&sums) {
+        let count = sum[4].max(1);
+        *pixel = [0, 1, 2, 3].map(|channel| (sum[channel] / count) as u8);
+    }
+    small
+}
+
+enum TextureSlot {
+    Unloaded,
+    Fetching(Coroutine<Result<Vec<u8>, String>>),
+    Ready(Texture2D),
+}
+
+// Scene textures are fetched when a scene gets close and dropped once it is far behind,
+// decoding at most one per frame so big photos do not stall the animation.
+// A small preview of every decoded texture is kept for the overview.
+pub struct SceneTextures {
+    paths: Vec<String>,
+    slots: Vec<TextureSlot>,
+    thumbnails: Vec<Option<Texture2D>>,
+    loading: Texture2D,
+}
+
+impl SceneTextures {
+    pub fn new(paths: Vec<String>) -> Self {
+        SceneTextures {
+            slots: paths.iter().map(|_| TextureSlot::Unloaded).collect(),
+            thumbnails: paths.iter().map(|_| None).collect(),
+            paths,
+            loading: Texture2D::from_image(&Image::gen_image_color(1, 1, BLANK)),
+        }
+    }
+
+    fn is_wanted(index: usize, current: usize) -> bool {
+        index + KEEP_BEHIND >= current && index <= current + PREFETCH_AHEAD
+    }
+
+    // `shown` stays loaded until the scene that is `current` next is ready to replace it
+    pub fn update(&mut self, shown: usize, current: usize, warnings: &mut Vec<String>) {
+        for (index, slot) in self.slots.iter_mut().enumerate() {
+            let wanted = index == shown || Self::is_wanted(index, current);
+            match slot {
+                TextureSlot::Unloaded if wanted => {
+                    *slot = TextureSlot::Fetching(start_image_fetch(&self.paths[index]))
+                }
+                // a finished fetch nobody needs anymore is still taken so its bytes are freed
+                TextureSlot::Fetching(file) if !wanted && file.is_done() => {
+                    let _ = take_fetched(file);
+                    *slot = TextureSlot::Unloaded;
+                }
+                TextureSlot::Ready(_) if !wanted => *slot = TextureSlot::Unloaded,
+                _ => {}
+            }
+        }
+
+        let nearest_first = (current..self.slots.len()).chain((0..current).rev());
+        for index in nearest_first {
+            if let TextureSlot::Fetching(file) = &self.slots[index] {
+                if file.is_done() {
+                    let image =
+                        image_or_placeholder(take_fetched(file), &self.paths[index], warnings);
+                    if self.thumbnails[index].is_none() {
+                        self.thumbnails[index] =
+                            Some(Texture2D::from_image(&shrink_image(&image, THUMBNAIL_SIZE)));
+                    }
+                    self.slots[index] = TextureSlot::Ready(Texture2D::from_image(&image));
+                    break;
+                }
+            }
+        }
+    }
+
+    pub fn is_ready(&self, index: usize) -> bool {
+        matches!(self.slots.get(index), Some(TextureSlot::Ready(_)))
+    }
+
+    // the editor picked another image for the scene
+    pub fn set_path(&mut self, index: usize, path: String) {
+        self.paths[index] = path;
+        self.slots[index] = TextureSlot::Unloaded;
+        self.thumbnails[index] = None;
+    }
+
+    pub fn thumbnail(&self, index: usize) -> Option<&Texture2D> {
+        self.thumbnails
+            .get(index)
+            .and_then(|thumbnail| thumbnail.as_ref())
+    }
+
+    // bytes held by the decoded scene textures and their thumbnails
+    pub fn memory(&self) -> usize {
+        self.slots
+            .iter()
+            .filter_map(|slot| match slot {
+                TextureSlot::Ready(texture) => Some(texture),
+                _ => None,
+            })
+            .chain(self.thumbnails.iter().flatten())
+            .map(|texture| texture_bytes(texture.size()))
+            .sum()
+    }
+
+    // an invisible texture while the real one is still loading
+    pub fn texture(&self, index: usize) -> &Texture2D {
+        match self.slots.get(index) {
+            Some(TextureSlot::Ready(texture)) => texture,
+            _ => &self.loading,
+        }
+    }
+}
+
+pub fn draw_heart_shape(x: f32, y: f32, size: f32, color: Color) {
+    let radius = size / 4.0;
+    draw_circle(x + radius, y + radius, radius, color);
+    draw_circle(x + radius * 3.0, y + radius, radius, color);
+    draw_triangle(
+        vec2(x + radius * 0.07, y + radius * 1.4),
+        vec2(x + size - radius * 0.07, y + radius * 1.4),
+        vec2(x + size / 2.0, y + size),
+        color,
+    );
+}
+
+pub fn draw_loading_screen(progress: f32) {
+    let (width, height) = (screen_width(), screen_height());
+    clear_background(Color::new(1.0, 0.85, 0.85, 1.0));
+
+    let size = (width / (PROGRESS_HEARTS as f32 * 1.5)).min(60.0);
+    let row_width = size * 1.5 * PROGRESS_HEARTS as f32 - size * 0.5;
+    let filled = progress.clamp(0.0, 1.0) * PROGRESS_HEARTS as f32;
+    for index in 0..PROGRESS_HEARTS {
+        let x = (width - row_width) / 2.0 + index as f32 * size * 1.5;
+        let y = (height - size) / 2.0;
+        let fill = (filled - index as f32).clamp(0.0, 1.0);
+        draw_heart_shape(x, y, size, Color::new(1.0, 1.0, 1.0, 0.8));
+        draw_heart_shape(x, y, size, Color::new(0.9, 0.1, 0.2, fill));
+    }
+
+    let label = format!("{}%", (progress * 100.0) as u32);
+    let label_size = measure_text(&label, None, 30, 1.0);
+    draw_text(
+        &label,
+        (width - label_size.width) / 2.0,
+        height / 2.0 + size * 1.5,
+        30.0,
+        Color::new(0.6, 0.1, 0.2, 1.0),
+    );
+}
+
+pub fn draw_error_screen(message: &str) {
+    clear_background(Color::new(0.2, 0.05, 0.1, 1.0));
+    draw_text("Something went wrong :(", 20.0, 50.0, 40.0, WHITE);
+    for (index, line) in message.lines().enumerate() {
+        draw_text(line, 20.0, 100.0 + index as f32 * 30.0, 24.0, PINK);
+    }
+}
+
+pub fn draw_warnings(warnings: &[String]) {
+    for (index, warning) in warnings.iter().enumerate() {
+        draw_text(warning, 10.0, 20.0 + index as f32 * 18.0, 18.0, RED);
+    }
+}
+
+// Fetches every file at once and decodes one finished file per frame,
+// so the progress bar keeps moving on both desktop and web.
+// Only the texture of the starting scene is waited for, the rest load while playing.
+pub async fn load_assets(story: &StorySource, start_scene: usize) -> Result<Assets, String> {
+    let mut requests = vec![match story {
+        StorySource::File(path) => start_request(AssetKind::Story, path),
+        StorySource::Link(payload) => {
+            let payload = payload.clone();
+            Request {
+                kind: AssetKind::Story,
+                path: "the story link".to_string(),
+                file: start_coroutine(async move { decode_story(&payload) }),
+            }
+        }
+    }];
+    for (index, path) in FONT_PATHS.iter().enumerate() {
+        requests.push(start_request(AssetKind::Font(index), path));
+    }
+    for (kind, path) in [(AssetKind::Heart, HEART_PATH), (AssetKind::Star, STAR_PATH)] {
+        requests.push(Request {
+            kind,
+            path: path.to_string(),
+            file: start_image_fetch(path),
+        });
+    }
+
+    let mut story: Option<Story> = None;
+    let mut fonts: Vec<Option<LoadedFont>> = FONT_PATHS.iter().map(|_| None).collect();
+    let mut heart_image = None;
+    let mut star_image = None;
+    let mut scene_textures: Option<SceneTextures> = None;
+    let mut warnings = vec![];
+    // the starting scene texture counts as one more file
+    let total = requests.len() + 1;
+    let mut loaded = 0;
+
+    loop {
+        let first_scene_ready = scene_textures
+            .as_ref()
+            .is_some_and(|textures| textures.is_ready(start_scene));
+        if requests.is_empty() && first_scene_ready {
+            break;
+        }
+        if let Some(textures) = &mut scene_textures {
+            textures.update(start_scene, start_scene, &mut warnings);
+        }
+
+        if let Some(position) = requests.iter().position(|request| request.file.is_done()) {
+            let request = requests.remove(position);
+            let bytes = take_fetched(&request.file);
+            loaded += 1;
+
+            match request.kind {
+                AssetKind::Story => {
+                    let loaded_story = bytes
+                        .and_then(|bytes| Story::from_json(&String::from_utf8_lossy(&bytes)))
+                        .map_err(|error| format!("{}: {}", request.path, error))?;
+                    if start_scene >= loaded_story.scenes.len() {
+                        return Err(format!(
+                            "cannot start from scene {}, the story has {} scenes",
+                            start_scene + 1,
+                            loaded_story.scenes.len()
+                        ));
+                    }
+                    scene_textures = Some(SceneTextures::new(
+                        loaded_story
+                            .scenes
+                            .iter()
+                            .map(|scene| scene.texture.clone())
+                            .collect(),
+                    ));
+                    story = Some(loaded_story);
+                }
+                AssetKind::Font(index) => match bytes.and_then(|bytes| font_from_bytes(&bytes)) {
+                    Ok(font) => fonts[index] = Some(font),
+                    Err(error) => warnings.push(format!("{}: {}", request.path, error)),
+                },
+                AssetKind::Heart => {
+                    heart_image = Some(image_or_placeholder(bytes, &request.path, &mut warnings))
+                }
+                AssetKind::Star => {
+                    star_image = Some(image_or_placeholder(bytes, &request.path, &mut warnings))
+                }
+            }
+        }
+
+        let progress = loaded + first_scene_ready as usize;
+        draw_loading_screen(progress as f32 / total as f32);
+        next_frame().await;
+    }
+
+    for warning in &warnings {
+        warn!("{}", warning);
+    }
+
+    // missing fonts are replaced by the first one that loaded so font indices stay valid
+    let fallback_font = fonts
+        .iter()
+        .flatten()
+        .next()
+        .cloned()
+        .ok_or_else(|| warnings.join("\n"))?;
+    let fonts = fonts
+        .into_iter()
+        .map(|font| font.unwrap_or_else(|| fallback_font.clone()))
+        .collect();
+
+    let heart_image = heart_image.unwrap_or_else(placeholder_image);
+    let star_image = star_image.unwrap_or_else(placeholder_image);
+
+    Ok(Assets {
+        story: story.ok_or("the story was not loaded")?,
+        fonts,
+        heart_texture: Texture2D::from_image(&heart_image),
+        star_texture: Texture2D::from_image(&star_image),
+        particles: SpriteBatch::new(&[&heart_image, &star_image]),
+        scene_textures: scene_textures.ok_or("the story was not loaded")?,
+        warnings,
+    })
+}
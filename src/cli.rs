@@ -0,0 +1,271 @@
+// Command line flags of the desktop build.
+
+use crate::asset_paths::STORY_PATH;
+use crate::assets::embedded_file;
+use crate::recording::SIMULATION_FPS;
+use crate::settings::{RenderSettings, Settings};
+use crate::share::{decode_story, encode_story, link_payload};
+use crate::story::Story;
+
+pub const HELP: &str = "\
+Usage: anniversary-game [OPTIONS]
+
+Options:
+  --story <PATH>         story file to play [default: story.json]
+  --story-link <LINK>    play the story packed in a link made with --print-link
+  --print-link           print the link fragment that carries the story file and exit
+  --scene <NUMBER>       scene to start from, counting from 1
+  --fullscreen           start in fullscreen
+  --size <WIDTHxHEIGHT>  window size in pixels, like 1280x720
+  --lang <CODE>          language of the texts, like ru or en
+  --seed <NUMBER>        seed for particles and colors, the same seed gives the same show
+  --mute                 start without sound
+  --autoplay             advance scenes on their own
+  --no-progress          hide the row of hearts counting the scenes
+  --screenshot-scale <N>
+                         screenshots taken with S and recorded frames are N times the
+                         window size [default: 1]
+  --render <PATH>        record the story to numbered png frames in a directory, or to
+                         a gif when PATH ends in .gif, then exit
+  --render-fps <N>       frames per second of the recording, 60 divided by a whole number
+                         [default: 30]
+  --render-seconds <S>   seconds every scene is recorded for [default: its autoplay time]
+  --debug                show frame times and scene details, F3 toggles them
+  -h, --help             print this help";
+
+// reasons to stop before the game starts
+pub enum CliExit {
+    Help,
+    // prints the link that carries the story file, see share.rs
+    PrintLink(String),
+    Invalid(String),
+}
+
+fn parse_number<T: std::str::FromStr>(flag: &str, value: &str) -> Result<T, CliExit> {
+    value
+        .parse()
+        .map_err(|_| CliExit::Invalid(format!("{flag} expects a number, got {value:?}")))
+}
+
+fn parse_size(value: &str) -> Result<(i32, i32), CliExit> {
+    let invalid = || CliExit::Invalid(format!("--size expects WIDTHxHEIGHT, got {value:?}"));
+    let (width, height) = value.split_once(['x', 'X']).ok_or_else(invalid)?;
+    match (width.parse(), height.parse()) {
+        (Ok(width), Ok(height)) if width > 0 && height > 0 => Ok((width, height)),
+        _ => Err(invalid()),
+    }
+}
+
+pub fn parse_args(args: impl IntoIterator<Item = String>) -> Result<Settings, CliExit> {
+    let mut settings = Settings::default();
+    let mut print_link = false;
+    let mut render_path = None;
+    let mut render_fps = 30;
+    let mut render_seconds = None;
+    let mut args = args.into_iter();
+
+    while let Some(arg) = args.next() {
+        // both "--lang en" and "--lang=en" work
+        let (flag, inline_value) = match arg.split_once('=') {
+            Some((flag, value)) if flag.starts_with("--") => {
+                (flag.to_string(), Some(value.to_string()))
+            }
+            _ => (arg, None),
+        };
+        let mut value = || {
+            inline_value
+                .clone()
+                .or_else(|| args.next())
+                .ok_or_else(|| CliExit::Invalid(format!("{flag} expects a value")))
+        };
+
+        match flag.as_str() {
+            "-h" | "--help" => return Err(CliExit::Help),
+            "--story" => settings.story_path = Some(value()?),
+            "--story-link" => settings.story_link = Some(link_payload(&value()?).to_string()),
+            "--print-link" => print_link = true,
+            "--scene" => {
+                let scene: usize = parse_number(&flag, &value()?)?;
+                if scene == 0 {
+                    return Err(CliExit::Invalid("scenes are counted from 1".to_string()));
+                }
+                settings.start_scene = Some(scene - 1);
+            }
+            "--fullscreen" => settings.fullscreen = true,
+            "--size" => settings.window_size = Some(parse_size(&value()?)?),
+            "--lang" => settings.language = Some(value()?),
+            "--seed" => settings.seed = Some(parse_number(&flag, &value()?)?),
+            "--mute" => settings.mute = true,
+            "--autoplay" => settings.autoplay = true,
+            "--no-progress" => settings.show_progress = false,
+            "--screenshot-scale" => {
+                let scale: f32 = parse_number(&flag, &value()?)?;
+                if !(scale.is_finite() && scale >= 1.0) {
+                    return Err(CliExit::Invalid(
+                        "--screenshot-scale has to be a number of at least 1".to_string(),
+                    ));
+                }
+                settings.screenshot_scale = scale;
+            }
+            "--debug" => settings.debug = true,
+            "--render" => render_path = Some(value()?),
+            "--render-fps" => {
+                render_fps = parse_number(&flag, &value()?)?;
+                if render_fps == 0 || !SIMULATION_FPS.is_multiple_of(render_fps) {
+                    return Err(CliExit::Invalid(format!(
+                        "--render-fps has to divide {SIMULATION_FPS}, like 30, 20 or 15"
+                    )));
+                }
+            }
+            "--render-seconds" => {
+                let seconds: f32 = parse_number(&flag, &value()?)?;
+                // nan and inf would keep the recording going forever
+                if !(seconds.is_finite() && seconds > 0.0) {
+                    return Err(CliExit::Invalid(
+                        "--render-seconds has to be a number more than 0".to_string(),
+                    ));
+                }
+                render_seconds = Some(seconds);
+            }
+            _ => return Err(CliExit::Invalid(format!("unknown option {flag:?}"))),
+        }
+    }
+    settings.render = render_path.map(|path| RenderSettings {
+        path,
+        fps: render_fps,
+        scene_seconds: render_seconds,
+    });
+    if print_link {
+        let path = settings.story_path.unwrap_or(STORY_PATH.to_string());
+        return Err(CliExit::PrintLink(path));
+    }
+    check_start_scene(&settings)?;
+    Ok(settings)
+}
+
+// --scene past the last scene, caught before a window opens
+fn check_start_scene(settings: &Settings) -> Result<(), CliExit> {
+    let Some(start_scene) = settings.start_scene else {
+        return Ok(());
+    };
+    let json = match &settings.story_link {
+        Some(payload) => decode_story(payload).ok(),
+        None => {
+            let path = settings.story_path.as_deref().unwrap_or(STORY_PATH);
+            std::fs::read(path)
+                .ok()
+                .or_else(|| embedded_file(path).map(<[u8]>::to_vec))
+        }
+    };
+    // a story that does not load is shown on the error screen instead
+    let Some(story) = json.and_then(|json| Story::from_json(&String::from_utf8_lossy(&json)).ok())
+    else {
+        return Ok(());
+    };
+    if start_scene >= story.scenes.len() {
+        return Err(CliExit::Invalid(format!(
+            "cannot start from scene {}, the story has {} scenes",
+            start_scene + 1,
+            story.scenes.len()
+        )));
+    }
+    Ok(())
+}
+
+pub fn story_link(story_path: &str) -> Result<String, String> {
+    let json =
+        std::fs::read_to_string(story_path).map_err(|error| format!("{story_path}: {error}"))?;
+    encode_story(&json).map_err(|error| format!("{story_path}: {error}"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(args: &[&str]) -> Result<Settings, CliExit> {
+        parse_args(args.iter().map(|arg| arg.to_string()))
+    }
+
+    fn invalid(args: &[&str]) -> bool {
+        matches!(parse(args), Err(CliExit::Invalid(_)))
+    }
+
+    #[test]
+    fn scenes_count_from_one() {
+        assert!(invalid(&["--scene", "0"]));
+        assert!(invalid(&["--scene", "-1"]));
+        assert_eq!(parse(&["--scene", "3"]).ok().unwrap().start_scene, Some(2));
+        let json = std::fs::read_to_string(STORY_PATH).unwrap();
+        let last = Story::from_json(&json).unwrap().scenes.len().to_string();
+        assert!(!invalid(&["--scene", &last]));
+        assert!(invalid(&["--scene=99", "--story", STORY_PATH]));
+    }
+
+    #[test]
+    fn size_needs_both_sides() {
+        assert!(invalid(&["--size", "10x"]));
+        assert!(invalid(&["--size", "x10"]));
+        assert!(invalid(&["--size", "0x10"]));
+        assert!(invalid(&["--size", "1280"]));
+        let settings = parse(&["--size", "1280X720"]).ok().unwrap();
+        assert_eq!(settings.window_size, Some((1280, 720)));
+    }
+
+    #[test]
+    fn values_inline_or_after_the_flag() {
+        assert_eq!(
+            parse(&["--lang=en"]).ok().unwrap().language.as_deref(),
+            Some("en")
+        );
+        assert_eq!(
+            parse(&["--lang", "ru"]).ok().unwrap().language.as_deref(),
+            Some("ru")
+        );
+        assert!(invalid(&["--lang"]));
+        assert!(invalid(&["--seed", "many"]));
+    }
+
+    #[test]
+    fn unknown_flags_and_help() {
+        assert!(invalid(&["--colour"]));
+        assert!(invalid(&["scene"]));
+        assert!(matches!(parse(&["--autoplay", "-h"]), Err(CliExit::Help)));
+        assert!(matches!(
+            parse(&["--print-link"]),
+            Err(CliExit::PrintLink(path)) if path == STORY_PATH
+        ));
+    }
+
+    #[test]
+    fn render_options() {
+        assert!(invalid(&["--render-fps", "0"]));
+        assert!(invalid(&["--render-fps", "25"]));
+        for seconds in ["0", "-2", "nan", "inf", "NaN"] {
+            assert!(invalid(&["--render-seconds", seconds]), "{seconds}");
+        }
+        let settings = parse(&["--render", "out.gif", "--render-fps", "20"])
+            .ok()
+            .unwrap();
+        let render = settings.render.unwrap();
+        assert_eq!((render.path.as_str(), render.fps), ("out.gif", 20));
+        assert_eq!(render.scene_seconds, None);
+        assert_eq!(
+            parse(&["--render", "frames", "--render-seconds=2.5"])
+                .ok()
+                .unwrap()
+                .render
+                .unwrap()
+                .scene_seconds,
+            Some(2.5)
+        );
+    }
+
+    #[test]
+    fn screenshot_scale_is_at_least_one() {
+        for scale in ["0.5", "nan", "inf"] {
+            assert!(invalid(&["--screenshot-scale", scale]), "{scale}");
+        }
+        let settings = parse(&["--screenshot-scale", "2"]).ok().unwrap();
+        assert_eq!(settings.screenshot_scale, 2.0);
+    }
+}
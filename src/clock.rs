@@ -0,0 +1,36 @@
+// Time as the scenes see it. Normally the real time; while the story is being
+// recorded it moves by the same step every frame, however long drawing took.
+
+use std::cell::Cell;
+
+use macroquad::prelude::{get_frame_time, get_time};
+
+thread_local! {
+    // (time, step) once a fixed step is in use
+    static FIXED: Cell<Option<(f64, f32)>> = const { Cell::new(None) };
+}
+
+pub fn use_fixed_step(step: f32) {
+    FIXED.with(|fixed| fixed.set(Some((0.0, step))));
+}
+
+// moves a fixed clock one step on, called once per frame
+pub fn advance() {
+    FIXED.with(|fixed| {
+        if let Some((time, step)) = fixed.get() {
+            fixed.set(Some((time + step as f64, step)));
+        }
+    });
+}
+
+pub fn time() -> f64 {
+    FIXED
+        .with(|fixed| fixed.get())
+        .map_or_else(get_time, |(time, _)| time)
+}
+
+pub fn frame_time() -> f32 {
+    FIXED
+        .with(|fixed| fixed.get())
+        .map_or_else(get_frame_time, |(_, step)| step)
+}
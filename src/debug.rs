@@ -0,0 +1,121 @@
+use std::collections::VecDeque;
+
+use macroquad::prelude::*;
+
+// frames kept for the frame time graph
+const GRAPH_FRAMES: usize = 120;
+const GRAPH_HEIGHT: f32 = 60.0;
+// the graph tops out at this many milliseconds, slower frames are cut off
+const GRAPH_MAX_MS: f32 = 50.0;
+const TARGET_FRAME_MS: f32 = 1000.0 / 60.0;
+const FONT_SIZE: f32 = 20.0;
+const LINE_HEIGHT: f32 = 22.0;
+const MARGIN: f32 = 10.0;
+const PANEL_WIDTH: f32 = 360.0;
+
+// what the overlay reports about the frame, gathered by the game
+pub struct DebugInfo<'a> {
+    pub particles: usize,
+    pub scene_index: usize,
+    pub scenes_amount: usize,
+    // the scene texture path, the closest thing scenes have to a name
+    pub scene_id: &'a str,
+    pub font_index: usize,
+    pub window_size: (f32, f32),
+    pub texture_size: Vec2,
+    pub texture_memory: usize,
+}
+
+// RGBA8, which is what every texture in the game is uploaded as
+pub fn texture_bytes(size: Vec2) -> usize {
+    size.x as usize * size.y as usize * 4
+}
+
+// Frame timings and scene state drawn over the game, toggled with F3 or ?debug.
+pub struct DebugOverlay {
+    pub open: bool,
+    frame_times: VecDeque<f32>,
+}
+
+impl DebugOverlay {
+    pub fn new(open: bool) -> Self {
+        DebugOverlay {
+            open,
+            frame_times: VecDeque::with_capacity(GRAPH_FRAMES),
+        }
+    }
+
+    // called every frame, also while closed so the graph is full when it opens
+    pub fn record_frame(&mut self) {
+        if self.frame_times.len() == GRAPH_FRAMES {
+            self.frame_times.pop_front();
+        }
+        self.frame_times.push_back(get_frame_time() * 1000.0);
+    }
+
+    pub fn draw(&self, info: &DebugInfo) {
+        let slowest = self.frame_times.iter().copied().fold(0.0, f32::max);
+        let lines = [
+            format!("{} fps, slowest frame {:.1} ms", get_fps(), slowest),
+            format!("particles: {}", info.particles),
+            format!(
+                "scene {} / {}: {}",
+                info.scene_index + 1,
+                info.scenes_amount,
+                info.scene_id
+            ),
+            format!("font: {}", info.font_index),
+            format!(
+                "window: {}x{}",
+                info.window_size.0 as u32, info.window_size.1 as u32
+            ),
+            format!(
+                "texture: {}x{}",
+                info.texture_size.x as u32, info.texture_size.y as u32
+            ),
+            format!(
+                "texture memory: {:.1} MiB",
+                info.texture_memory as f32 / (1024.0 * 1024.0)
+            ),
+        ];
+
+        let text_height = lines.len() as f32 * LINE_HEIGHT;
+        draw_rectangle(
+            MARGIN,
+            MARGIN,
+            PANEL_WIDTH,
+            text_height + GRAPH_HEIGHT + MARGIN * 3.0,
+            Color::new(0.0, 0.0, 0.0, 0.6),
+        );
+        for (index, line) in lines.iter().enumerate() {
+            draw_text(
+                line,
+                MARGIN * 2.0,
+                MARGIN + LINE_HEIGHT * (index + 1) as f32,
+                FONT_SIZE,
+                WHITE,
+            );
+        }
+
+        let graph_bottom = MARGIN * 2.0 + text_height + GRAPH_HEIGHT;
+        let bar_width = (PANEL_WIDTH - MARGIN * 2.0) / GRAPH_FRAMES as f32;
+        for (index, frame_ms) in self.frame_times.iter().enumerate() {
+            let height = frame_ms.min(GRAPH_MAX_MS) / GRAPH_MAX_MS * GRAPH_HEIGHT;
+            let color = if *frame_ms > TARGET_FRAME_MS * 1.5 {
+                RED
+            } else {
+                GREEN
+            };
+            draw_rectangle(
+                MARGIN * 2.0 + index as f32 * bar_width,
+                graph_bottom - height,
+                bar_width,
+                height,
+                color,
+            );
+        }
+        // where a frame at 60 fps ends
+        let target_y = graph_bottom - TARGET_FRAME_MS / GRAPH_MAX_MS * GRAPH_HEIGHT;
+        draw_line(MARGIN * 2.0, target_y, PANEL_WIDTH, target_y, 1.0, YELLOW);
+    }
+}
@@ -0,0 +1,367 @@
+use macroquad::{
+    hash,
+    prelude::*,
+    ui::{root_ui, Ui},
+};
+
+use crate::assets::StorySource;
+use crate::palette::{Palette, PaletteColors, PalettePreset, MAX_PARTICLES, PRESETS};
+use crate::share::encode_story;
+use crate::story::{Story, WhatDraw};
+
+const WINDOW_POSITION: Vec2 = vec2(10.0, 10.0);
+const WINDOW_SIZE: Vec2 = vec2(380.0, 700.0);
+// the rgb mask palettes work on a 0..1000 scale in the bundled story
+const PALETTE_SCALE: f32 = 2000.0;
+
+// what the editor touched this frame, so the game can refresh what depends on it
+#[derive(Default)]
+pub struct EditorChanges {
+    pub texts: bool,
+    pub palette: bool,
+    pub texture: bool,
+    // scenes were inserted, deleted or moved, the value is the scene to show now
+    pub scenes: Option<usize>,
+    pub jump: Option<usize>,
+    // taken next frame, without the editor in it
+    pub screenshot: bool,
+}
+
+// Controls drawn over the current scene, toggled with F2.
+pub struct Editor {
+    pub open: bool,
+    images: Vec<String>,
+    status: String,
+}
+
+// every image in images/ on desktop, the ones the story already uses on the web
+fn available_images(story: &Story) -> Vec<String> {
+    let mut images: Vec<String> = story
+        .scenes
+        .iter()
+        .map(|scene| scene.texture.clone())
+        .collect();
+    #[cfg(not(target_arch = "wasm32"))]
+    if let Ok(entries) = std::fs::read_dir("images") {
+        for entry in entries.flatten() {
+            let name = entry.file_name().to_string_lossy().to_string();
+            let lowercase = name.to_lowercase();
+            if lowercase.ends_with(".png")
+                || lowercase.ends_with(".jpg")
+                || lowercase.ends_with(".jpeg")
+            {
+                images.push(format!("images/{name}"));
+            }
+        }
+    }
+    images.sort();
+    images.dedup();
+    images
+}
+
+fn color_sliders(ui: &mut Ui, label: &str, color: &mut Color) {
+    ui.label(None, label);
+    ui.slider(hash!(label, "r"), "red", 0.0..1.0, &mut color.r);
+    ui.slider(hash!(label, "g"), "green", 0.0..1.0, &mut color.g);
+    ui.slider(hash!(label, "b"), "blue", 0.0..1.0, &mut color.b);
+}
+
+fn palette_kind(colors: &PaletteColors) -> usize {
+    match colors {
+        PaletteColors::Mask { .. } => 0,
+        PaletteColors::Hsv { .. } => 1,
+        PaletteColors::Preset { .. } => 2,
+        PaletteColors::List { .. } => 3,
+        PaletteColors::Gradient { .. } => 4,
+    }
+}
+
+// what a palette starts as when the editor switches its kind
+fn default_palette_colors(kind: usize) -> PaletteColors {
+    match kind {
+        0 => PaletteColors::Mask {
+            main_color_range: (500.0, 1000.0),
+            other_color_multiplier: 100.0,
+            rgb_distribution: (true, false, false),
+        },
+        1 => PalettePreset::Rose.colors(),
+        2 => PaletteColors::Preset {
+            preset: PalettePreset::Rose,
+        },
+        3 => PaletteColors::List {
+            colors: vec![RED, PINK, WHITE],
+        },
+        _ => PaletteColors::Gradient {
+            gradient: vec![PINK, GOLD],
+        },
+    }
+}
+
+fn range_sliders(ui: &mut Ui, label: &str, range: std::ops::Range<f32>, value: &mut (f32, f32)) {
+    ui.slider(
+        hash!(label, "from"),
+        &format!("{label} from"),
+        range.clone(),
+        &mut value.0,
+    );
+    ui.slider(
+        hash!(label, "to"),
+        &format!("{label} to"),
+        range,
+        &mut value.1,
+    );
+    // gen_range panics on an empty range
+    value.0 = value.0.min(value.1);
+}
+
+fn palette_controls(ui: &mut Ui, palette: &mut Palette) {
+    let mut amount = palette.amount as f32;
+    ui.slider(hash!(), "amount", 0.0..MAX_PARTICLES as f32, &mut amount);
+    palette.amount = amount.round() as usize;
+
+    let mut kind = palette_kind(&palette.colors);
+    ui.combo_box(
+        hash!(),
+        "colors",
+        &["rgb mask", "hsv", "preset", "list", "gradient"],
+        &mut kind,
+    );
+    if kind != palette_kind(&palette.colors) {
+        palette.colors = default_palette_colors(kind);
+    }
+
+    match &mut palette.colors {
+        PaletteColors::Mask {
+            main_color_range,
+            other_color_multiplier,
+            rgb_distribution,
+        } => {
+            range_sliders(ui, "main", 1.0..PALETTE_SCALE, main_color_range);
+            ui.slider(
+                hash!(),
+                "others",
+                0.0..PALETTE_SCALE,
+                other_color_multiplier,
+            );
+            ui.checkbox(hash!(), "red", &mut rgb_distribution.0);
+            ui.checkbox(hash!(), "green", &mut rgb_distribution.1);
+            ui.checkbox(hash!(), "blue", &mut rgb_distribution.2);
+        }
+        PaletteColors::Hsv {
+            hue,
+            saturation,
+            value,
+        } => {
+            range_sliders(ui, "hue", -180.0..540.0, hue);
+            range_sliders(ui, "saturation", 0.0..1.0, saturation);
+            range_sliders(ui, "value", 0.0..1.0, value);
+        }
+        PaletteColors::Preset { preset } => {
+            let names = PRESETS.map(|preset| preset.name());
+            let mut index = PRESETS
+                .iter()
+                .position(|known| known == preset)
+                .unwrap_or(0);
+            ui.combo_box(hash!(), "preset", &names, &mut index);
+            *preset = PRESETS[index];
+        }
+        PaletteColors::List { colors: stops } | PaletteColors::Gradient { gradient: stops } => {
+            for (index, color) in stops.iter_mut().enumerate() {
+                color_sliders(ui, &format!("Color {}", index + 1), color);
+            }
+            if ui.button(None, "Add color") {
+                stops.push(WHITE);
+            }
+            ui.same_line(0.0);
+            if ui.button(None, "Remove color") && stops.len() > 1 {
+                stops.pop();
+            }
+        }
+    }
+}
+
+fn save(story: &Story, source: &StorySource) -> Result<String, String> {
+    let json = serde_json::to_string_pretty(story).map_err(|error| error.to_string())?;
+    match source {
+        // a link story has no file to go back to, so it becomes a new link
+        StorySource::Link(_) => {
+            let link = encode_story(&json)?;
+            info!("#{}", link);
+            Ok("the new link is in the console".to_string())
+        }
+        #[cfg(not(target_arch = "wasm32"))]
+        StorySource::File(path) => std::fs::write(path, json + "\n")
+            .map(|_| format!("saved to {path}"))
+            .map_err(|error| format!("{path}: {error}")),
+        #[cfg(target_arch = "wasm32")]
+        StorySource::File(_) => Err("saving to a file needs the desktop build".to_string()),
+    }
+}
+
+impl Editor {
+    pub fn new(story: &Story) -> Self {
+        Editor {
+            open: false,
+            images: available_images(story),
+            status: String::new(),
+        }
+    }
+
+    fn scene_controls(
+        &mut self,
+        ui: &mut Ui,
+        story: &mut Story,
+        scene_index: usize,
+        changes: &mut EditorChanges,
+    ) {
+        let scenes_amount = story.scenes.len();
+        ui.label(
+            None,
+            &format!("Scene {} / {}", scene_index + 1, scenes_amount),
+        );
+        if ui.button(None, "Previous") && scene_index > 0 {
+            changes.jump = Some(scene_index - 1);
+        }
+        ui.same_line(0.0);
+        if ui.button(None, "Next") && scene_index + 1 < scenes_amount {
+            changes.jump = Some(scene_index + 1);
+        }
+
+        if ui.button(None, "Insert copy") {
+            let copy = story.scenes[scene_index].clone();
+            story.scenes.insert(scene_index + 1, copy);
+            changes.scenes = Some(scene_index + 1);
+        }
+        ui.same_line(0.0);
+        if ui.button(None, "Delete") && scenes_amount > 1 {
+            story.scenes.remove(scene_index);
+            changes.scenes = Some(scene_index.min(scenes_amount - 2));
+        }
+        ui.same_line(0.0);
+        if ui.button(None, "Move up") && scene_index > 0 {
+            story.scenes.swap(scene_index, scene_index - 1);
+            changes.scenes = Some(scene_index - 1);
+        }
+        ui.same_line(0.0);
+        if ui.button(None, "Move down") && scene_index + 1 < scenes_amount {
+            story.scenes.swap(scene_index, scene_index + 1);
+            changes.scenes = Some(scene_index + 1);
+        }
+    }
+
+    fn text_controls(
+        ui: &mut Ui,
+        story: &mut Story,
+        scene_index: usize,
+        language: &str,
+        changes: &mut EditorChanges,
+    ) {
+        let scene = &mut story.scenes[scene_index];
+        // a missing translation starts as a copy of the default language,
+        // kept only once something in it is edited
+        let mut texts = scene.texts_for(language, &story.default_language).to_vec();
+        let mut edited = false;
+
+        ui.label(None, &format!("Texts ({language})"));
+        for (index, line) in texts.iter_mut().enumerate() {
+            let before = line.clone();
+            ui.input_text(hash!("line", index), "", line);
+            edited |= *line != before;
+        }
+        if ui.button(None, "Add line") {
+            texts.push(String::new());
+            edited = true;
+        }
+        ui.same_line(0.0);
+        if ui.button(None, "Remove line") && texts.pop().is_some() {
+            edited = true;
+        }
+
+        if edited {
+            scene.texts.insert(language.to_string(), texts);
+            changes.texts = true;
+        }
+    }
+
+    fn look_controls(
+        &self,
+        ui: &mut Ui,
+        story: &mut Story,
+        scene_index: usize,
+        changes: &mut EditorChanges,
+    ) {
+        let scene = &mut story.scenes[scene_index];
+        if let Some(theme) = &scene.theme {
+            ui.label(None, &format!("Theme \"{theme}\", changes override it"));
+        }
+        color_sliders(ui, "Background", &mut scene.bg_color);
+        color_sliders(ui, "Text", &mut scene.text_colors.0);
+        color_sliders(ui, "Text outline and shadow", &mut scene.text_colors.1);
+
+        ui.separator();
+        let mut kind = match scene.what_draw {
+            WhatDraw::Hearts => 0,
+            WhatDraw::Stars => 1,
+        };
+        ui.combo_box(hash!(), "particles", &["hearts", "stars"], &mut kind);
+        let what_draw = if kind == 0 {
+            WhatDraw::Hearts
+        } else {
+            WhatDraw::Stars
+        };
+        if what_draw != scene.what_draw {
+            scene.what_draw = what_draw;
+            changes.palette = true;
+        }
+
+        let names: Vec<&str> = self.images.iter().map(|image| image.as_str()).collect();
+        let mut image = names
+            .iter()
+            .position(|name| *name == scene.texture)
+            .unwrap_or(0);
+        ui.combo_box(hash!(), "texture", &names, &mut image);
+        if let Some(name) = names.get(image).filter(|name| **name != scene.texture) {
+            scene.texture = name.to_string();
+            changes.texture = true;
+        }
+
+        ui.separator();
+        let before = scene.palette.clone();
+        palette_controls(ui, &mut scene.palette);
+        changes.palette |= scene.palette != before;
+    }
+
+    pub fn draw(
+        &mut self,
+        story: &mut Story,
+        scene_index: usize,
+        language: &str,
+        source: &StorySource,
+    ) -> EditorChanges {
+        let mut changes = EditorChanges::default();
+        root_ui().window(hash!(), WINDOW_POSITION, WINDOW_SIZE, |ui| {
+            self.scene_controls(ui, story, scene_index, &mut changes);
+            if changes.scenes.is_some() {
+                return;
+            }
+            ui.separator();
+            Self::text_controls(ui, story, scene_index, language, &mut changes);
+            ui.separator();
+            self.look_controls(ui, story, scene_index, &mut changes);
+            ui.separator();
+            if ui.button(None, "Save") {
+                self.status = match save(story, source) {
+                    Ok(message) => message,
+                    Err(error) => error,
+                };
+            }
+            ui.same_line(0.0);
+            changes.screenshot = ui.button(None, "Screenshot");
+            ui.label(None, &self.status);
+        });
+        if changes.texture {
+            self.images = available_images(story);
+        }
+        changes
+    }
+}
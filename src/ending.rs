@@ -0,0 +1,245 @@
+use std::collections::BTreeMap;
+
+use macroquad::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::clock;
+use crate::markup::{parse_texts, RichLine};
+use crate::story::Story;
+use crate::text::{
+    draw_text_block, Alignment, InlineIcons, TextColors, TextFonts, TextStyle, VerticalAnchor,
+};
+
+const CREDITS_SIZE: u16 = 60;
+// pixels per second
+const SCROLL_SPEED: f32 = 60.0;
+const BUTTON_SIZE: f32 = 64.0;
+const BUTTON_MARGIN: f32 = 24.0;
+const DEFAULT_STATS: [&str; 3] = [
+    "Time together: {time}",
+    "Scenes seen: {scenes} of {total}",
+    "Clicks on pictures: {clicks}",
+];
+
+// What the ending shows, by language code like scene texts.
+// Stats lines can use {time}, {scenes}, {total} and {clicks}. There are no
+// mini-game scores or popped hearts to count, the game has neither.
+#[derive(Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct EndingConfig {
+    // the first line of every scene when empty
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub credits: BTreeMap<String, Vec<String>>,
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub stats: BTreeMap<String, Vec<String>>,
+}
+
+impl EndingConfig {
+    pub fn is_empty(&self) -> bool {
+        self.credits.is_empty() && self.stats.is_empty()
+    }
+}
+
+fn for_language<'a>(
+    texts: &'a BTreeMap<String, Vec<String>>,
+    language: &str,
+    default_language: &str,
+) -> Option<&'a Vec<String>> {
+    texts.get(language).or_else(|| texts.get(default_language))
+}
+
+// minutes and seconds, like 12:05
+fn format_time(seconds: f64) -> String {
+    let seconds = seconds as u64;
+    format!("{}:{:02}", seconds / 60, seconds % 60)
+}
+
+pub enum EndingAction {
+    Replay,
+    // open the overview to pick a scene again
+    Revisit,
+}
+
+// Scrolling credits and what happened while playing, shown after the last scene.
+pub struct Ending {
+    pub open: bool,
+    // reset when the story is replayed
+    started: f64,
+    pub picture_clicks: usize,
+    opened_at: f64,
+    lines: Vec<RichLine>,
+    // measured while drawing, how far the credits scroll before starting over
+    height: f32,
+}
+
+fn button_rects(window_size: (f32, f32)) -> (Rect, Rect) {
+    let y = window_size.1 - BUTTON_SIZE - BUTTON_MARGIN;
+    (
+        Rect::new(BUTTON_MARGIN, y, BUTTON_SIZE, BUTTON_SIZE),
+        Rect::new(
+            window_size.0 - BUTTON_SIZE - BUTTON_MARGIN,
+            y,
+            BUTTON_SIZE,
+            BUTTON_SIZE,
+        ),
+    )
+}
+
+// an arrow going around, to play again
+fn draw_replay_icon(rect: Rect) {
+    let center = rect.center();
+    let radius = rect.w * 0.28;
+    let point = |angle: f32| center + vec2(angle.cos(), angle.sin()) * radius;
+    let segments = 24;
+    for segment in 0..segments {
+        let start = 0.6 + segment as f32 / segments as f32 * 4.8;
+        let end = 0.6 + (segment + 1) as f32 / segments as f32 * 4.8;
+        let (from, to) = (point(start), point(end));
+        draw_line(from.x, from.y, to.x, to.y, 4.0, WHITE);
+    }
+    let tip = point(0.6);
+    draw_triangle(
+        tip + vec2(-9.0, -2.0),
+        tip + vec2(9.0, -2.0),
+        tip + vec2(0.0, 10.0),
+        WHITE,
+    );
+}
+
+// a grid of little pictures, like the overview
+fn draw_revisit_icon(rect: Rect) {
+    let cell = rect.w * 0.18;
+    let start = rect.center() - vec2(cell * 1.5 + 3.0, cell * 1.5 + 3.0);
+    for row in 0..3 {
+        for column in 0..3 {
+            draw_rectangle(
+                start.x + column as f32 * (cell + 3.0),
+                start.y + row as f32 * (cell + 3.0),
+                cell,
+                cell,
+                WHITE,
+            );
+        }
+    }
+}
+
+fn draw_button(rect: Rect, draw_icon: fn(Rect)) -> bool {
+    draw_rectangle(
+        rect.x,
+        rect.y,
+        rect.w,
+        rect.h,
+        Color::new(0.0, 0.0, 0.0, 0.4),
+    );
+    draw_icon(rect);
+    is_mouse_button_pressed(MouseButton::Left) && rect.contains(mouse_position().into())
+}
+
+impl Ending {
+    pub fn new() -> Self {
+        Ending {
+            open: false,
+            started: clock::time(),
+            picture_clicks: 0,
+            opened_at: 0.0,
+            lines: vec![],
+            height: 0.0,
+        }
+    }
+
+    pub fn show(&mut self, story: &Story, language: &str, visited: &[bool]) {
+        let config = &story.ending;
+        let credits: Vec<String> =
+            match for_language(&config.credits, language, &story.default_language) {
+                Some(credits) => credits.clone(),
+                None => story
+                    .scenes
+                    .iter()
+                    .filter_map(|scene| {
+                        scene
+                            .texts_for(language, &story.default_language)
+                            .first()
+                            .cloned()
+                    })
+                    .collect(),
+            };
+        let stats: Vec<String> =
+            match for_language(&config.stats, language, &story.default_language) {
+                Some(stats) => stats.clone(),
+                None => DEFAULT_STATS.iter().map(|line| line.to_string()).collect(),
+            };
+
+        let filled: Vec<String> = stats
+            .iter()
+            .map(|line| {
+                line.replace("{time}", &format_time(clock::time() - self.started))
+                    .replace(
+                        "{scenes}",
+                        &visited.iter().filter(|seen| **seen).count().to_string(),
+                    )
+                    .replace("{total}", &visited.len().to_string())
+                    .replace("{clicks}", &self.picture_clicks.to_string())
+            })
+            .collect();
+
+        self.lines = parse_texts(&[credits, vec![String::new()], filled].concat());
+        self.opened_at = clock::time();
+        self.open = true;
+    }
+
+    // playing again from the first scene counts everything anew
+    pub fn restart(&mut self) {
+        self.open = false;
+        self.started = clock::time();
+        self.picture_clicks = 0;
+    }
+
+    pub fn draw(
+        &mut self,
+        fonts: &TextFonts,
+        icons: &InlineIcons,
+        window_size: (f32, f32),
+    ) -> Option<EndingAction> {
+        draw_rectangle(
+            0.0,
+            0.0,
+            window_size.0,
+            window_size.1,
+            Color::new(0.1, 0.02, 0.05, 0.7),
+        );
+
+        // the credits come in from the bottom and start over once they left at the top
+        let cycle = window_size.1 + self.height + CREDITS_SIZE as f32;
+        let scrolled = ((clock::time() - self.opened_at) as f32 * SCROLL_SPEED) % cycle;
+        let style = TextStyle {
+            anchor: VerticalAnchor::Top,
+            offset: window_size.1 + CREDITS_SIZE as f32 - scrolled,
+            alignment: Alignment::Center,
+            ..Default::default()
+        };
+        self.height = draw_text_block(
+            &self.lines,
+            fonts,
+            icons,
+            CREDITS_SIZE,
+            window_size,
+            &style,
+            TextColors {
+                primary: WHITE,
+                secondary: BLACK,
+                tint: WHITE,
+            },
+        );
+
+        let (replay, revisit) = button_rects(window_size);
+        let replay = draw_button(replay, draw_replay_icon);
+        let revisit = draw_button(revisit, draw_revisit_icon);
+        if replay {
+            Some(EndingAction::Replay)
+        } else if revisit {
+            Some(EndingAction::Revisit)
+        } else {
+            None
+        }
+    }
+}
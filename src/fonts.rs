@@ -0,0 +1,102 @@
+use macroquad::prelude::*;
+
+use crate::markup::{parse_line, Span};
+use crate::story::Story;
+
+#[derive(Clone)]
+pub struct LoadedFont {
+    pub font: Font,
+    // macroquad does not expose the glyph table, so keep our own parsed copy
+    glyphs: fontdue::Font,
+}
+
+impl LoadedFont {
+    pub fn has_glyph(&self, letter: char) -> bool {
+        letter.is_whitespace() || self.glyphs.lookup_glyph_index(letter) != 0
+    }
+}
+
+pub fn font_from_bytes(bytes: &[u8]) -> Result<LoadedFont, String> {
+    let font = load_ttf_font_from_bytes(bytes).map_err(|error| error.to_string())?;
+    let glyphs = fontdue::Font::from_bytes(bytes, fontdue::FontSettings::default())
+        .map_err(|error| error.to_string())?;
+    Ok(LoadedFont { font, glyphs })
+}
+
+// first font of the stack that can draw the letter, the primary one when none can
+pub fn pick_font<'a>(
+    letter: char,
+    primary: &'a LoadedFont,
+    stack: &[&'a LoadedFont],
+) -> &'a LoadedFont {
+    if primary.has_glyph(letter) {
+        return primary;
+    }
+    stack
+        .iter()
+        .find(|font| font.has_glyph(letter))
+        .copied()
+        .unwrap_or(primary)
+}
+
+// splits the text into pieces that are drawn with a single font each
+pub fn font_runs<'t, 'a>(
+    text: &'t str,
+    primary: &'a LoadedFont,
+    stack: &[&'a LoadedFont],
+) -> Vec<(&'t str, &'a LoadedFont)> {
+    let mut runs: Vec<(&'t str, &'a LoadedFont)> = vec![];
+    let mut run_start = 0;
+    let mut run_font: Option<&'a LoadedFont> = None;
+
+    for (position, letter) in text.char_indices() {
+        let font = match run_font {
+            Some(font) if letter.is_whitespace() => font,
+            _ => pick_font(letter, primary, stack),
+        };
+        match run_font {
+            Some(current) if std::ptr::eq(current, font) => {}
+            Some(current) => {
+                runs.push((&text[run_start..position], current));
+                run_start = position;
+                run_font = Some(font);
+            }
+            None => run_font = Some(font),
+        }
+    }
+    if let Some(font) = run_font {
+        runs.push((&text[run_start..], font));
+    }
+    runs
+}
+
+pub fn missing_letters(text: &str, fonts: &[LoadedFont]) -> Vec<char> {
+    let mut missing: Vec<char> = vec![];
+    for span in parse_line(text).spans {
+        let Span::Text { text, .. } = span else {
+            continue;
+        };
+        for letter in text.chars() {
+            if !fonts.iter().any(|font| font.has_glyph(letter)) && !missing.contains(&letter) {
+                missing.push(letter);
+            }
+        }
+    }
+    missing
+}
+
+pub fn report_missing_glyphs(story: &Story, fonts: &[LoadedFont]) {
+    for (scene_index, scene) in story.scenes.iter().enumerate() {
+        for (language, texts) in &scene.texts {
+            for text in texts {
+                let missing = missing_letters(text, fonts);
+                if !missing.is_empty() {
+                    warn!(
+                        "scene {} ({}): no font can render {:?} in \"{}\"",
+                        scene_index, language, missing, text
+                    );
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,143 @@
+// Texts that appear as if written with a pen. The line being written is cut off
+// where the pen is by a shader, so its shadow, outline and icons are cut too.
+
+use macroquad::miniquad::{BlendFactor, BlendState, BlendValue, Equation};
+use macroquad::prelude::*;
+use serde::{Deserialize, Serialize};
+
+const VERTEX: &str = r#"#version 100
+attribute vec3 position;
+attribute vec2 texcoord;
+attribute vec4 color0;
+
+varying lowp vec2 uv;
+varying lowp vec4 color;
+// before the text pose moves it, where the cut is measured
+varying mediump float text_x;
+
+uniform mat4 Model;
+uniform mat4 Projection;
+
+void main() {
+    gl_Position = Projection * Model * vec4(position, 1);
+    color = color0 / 255.0;
+    uv = texcoord;
+    text_x = position.x;
+}"#;
+
+const FRAGMENT: &str = r#"#version 100
+precision mediump float;
+
+varying lowp vec2 uv;
+varying lowp vec4 color;
+varying mediump float text_x;
+
+uniform sampler2D Texture;
+uniform float cut_x;
+
+void main() {
+    // a pixel wide soft edge instead of a jagged one
+    float written = clamp(cut_x - text_x, 0.0, 1.0);
+    gl_FragColor = color * texture2D(Texture, uv) * vec4(1.0, 1.0, 1.0, written);
+}"#;
+
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Handwriting {
+    // pixels along the lines written per second
+    pub speed: f32,
+    // seconds after the scene starts before the pen touches the paper
+    pub delay: f32,
+    pub pen: bool,
+}
+
+impl Default for Handwriting {
+    fn default() -> Self {
+        Handwriting {
+            speed: 300.0,
+            delay: 0.5,
+            pen: true,
+        }
+    }
+}
+
+impl Handwriting {
+    // how far along the lines the pen got, line after line
+    pub fn written(&self, elapsed: f32) -> f32 {
+        (elapsed - self.delay).max(0.0) * self.speed
+    }
+
+    // seconds until the pen is done with lines this wide
+    pub fn duration(&self, line_widths: &[f32]) -> f32 {
+        self.delay + line_widths.iter().sum::<f32>() / self.speed.max(1.0)
+    }
+}
+
+// The material drawing the line under the pen. None when the shader did not
+// compile, then that line is drawn whole.
+pub struct HandwritingClip {
+    material: Option<Material>,
+}
+
+impl HandwritingClip {
+    pub fn new() -> Self {
+        let material = load_material(
+            ShaderSource::Glsl {
+                vertex: VERTEX,
+                fragment: FRAGMENT,
+            },
+            MaterialParams {
+                pipeline_params: PipelineParams {
+                    color_blend: Some(BlendState::new(
+                        Equation::Add,
+                        BlendFactor::Value(BlendValue::SourceAlpha),
+                        BlendFactor::OneMinusValue(BlendValue::SourceAlpha),
+                    )),
+                    ..Default::default()
+                },
+                uniforms: vec![("cut_x".to_string(), UniformType::Float1)],
+                ..Default::default()
+            },
+        );
+        if let Err(error) = &material {
+            warn!("handwriting shader: {error}");
+        }
+        HandwritingClip {
+            material: material.ok(),
+        }
+    }
+
+    // everything drawn until `end` is hidden right of `cut_x`
+    pub fn begin(&self, cut_x: f32) {
+        if let Some(material) = &self.material {
+            material.set_uniform("cut_x", cut_x);
+            gl_use_material(material);
+        }
+    }
+
+    pub fn end(&self) {
+        if self.material.is_some() {
+            gl_use_default_material();
+        }
+    }
+}
+
+// a slanted pen with its nib at `tip`, going up and down a little as it writes
+pub fn draw_pen(tip: Vec2, line_height: f32, elapsed: f32, body: Color, nib: Color) {
+    let tip = tip - vec2(0.0, line_height * (0.3 + 0.2 * (elapsed * 14.0).sin()));
+    let direction = vec2(0.5, -0.87);
+    let across = vec2(-direction.y, direction.x);
+    let nib_length = line_height * 0.35;
+    let width = line_height * 0.14;
+    let nib_end = tip + direction * nib_length;
+    let body_end = nib_end + direction * line_height * 1.4;
+
+    draw_triangle(
+        tip,
+        nib_end + across * width / 2.0,
+        nib_end - across * width / 2.0,
+        nib,
+    );
+    draw_line(nib_end.x, nib_end.y, body_end.x, body_end.y, width, body);
+    draw_circle(body_end.x, body_end.y, width / 2.0, body);
+}
@@ -1,15 +1,60 @@
-use std::vec;
-
+mod animation;
+mod asset_paths;
+mod assets;
+#[cfg(not(target_arch = "wasm32"))]
+mod cli;
+mod clock;
+mod debug;
+mod editor;
+mod ending;
+mod fonts;
+mod handwriting;
+mod markup;
+mod overview;
+mod palette;
+mod parallax;
+mod particles;
+mod platform;
+mod recording;
+mod screenshot;
+mod scroll;
+mod settings;
+mod share;
+mod slideshow;
+mod story;
+mod text;
+mod theme;
+
+use animation::{sample, tint};
+use asset_paths::STORY_PATH;
+use assets::{draw_error_screen, draw_warnings, load_assets, Assets, SceneTextures, StorySource};
+use debug::{texture_bytes, DebugInfo, DebugOverlay};
+use editor::Editor;
+use ending::{Ending, EndingAction};
+
+use fonts::report_missing_glyphs;
+use handwriting::HandwritingClip;
 use macroquad::{miniquad::window::screen_size, prelude::*, rand};
+use markup::parse_texts;
+use overview::{draw_overview, draw_overview_button, draw_progress, is_overview_button_clicked};
+use parallax::draw_layers;
+use particles::{Sprite, SpriteBatch};
+use recording::{Recorder, SIMULATION_FPS};
+use screenshot::Screenshot;
+use scroll::TextScroller;
+use settings::Settings;
+use slideshow::{default_duration, Slideshow};
+use story::WhatDraw;
+use text::{
+    draw_text_written, line_widths, FontCycler, InlineIcons, TextColors, TextFonts, Writing,
+};
 
 const WINDOW_HEIGHT: i32 = 1000;
 const WINDOW_WIDTH: i32 = 1000;
-const MAX_ROTATION: f32 = 2.0 * 3.14;
+const MAX_ROTATION: f32 = std::f32::consts::TAU;
 
-const HEARTS_AMOUNT: usize = 100;
-const STARS_AMOUNT: usize = 150;
-const LINES_SPACING: f32 = 15.0;
 const TEXT_SIZE: u16 = 90;
+const BOLD_FONT_INDEX: usize = 2;
 
 struct Heart {
     x: f32,
@@ -24,49 +69,33 @@ struct Star {
     rotation: f32,
 }
 
-enum WhatDraw {
-    Hearts,
-    Stars,
-}
-
-struct SceneConfig {
-    what_draw: WhatDraw,
-    bg_color: Color,
-    texture: Texture2D,
-    texts: Vec<&'static str>,
-    text_colors: (Color, Color),
-    colors: Vec<Color>,
-}
-
 fn window_conf() -> Conf {
+    let settings = Settings::from_environment();
+    let (window_width, window_height) = settings
+        .window_size
+        .unwrap_or((WINDOW_WIDTH, WINDOW_HEIGHT));
     Conf {
         window_title: "Love".to_string(),
-        window_height: WINDOW_HEIGHT,
-        window_width: WINDOW_WIDTH,
+        window_height,
+        window_width,
+        fullscreen: settings.fullscreen,
         window_resizable: true,
         ..Default::default()
     }
 }
 
-fn draw_hearts(heart_texture: &Texture2D, hearts: &Vec<Heart>) {
+fn draw_hearts(particles: &mut SpriteBatch, hearts: &[Heart]) {
     for heart in hearts {
-        draw_texture(heart_texture, heart.x, heart.y, heart.color);
+        particles.push(Sprite::Heart, heart.x, heart.y, 0.0, heart.color);
     }
+    particles.draw();
 }
 
-fn draw_stars(star_texture: &Texture2D, stars: &Vec<Star>) {
+fn draw_stars(particles: &mut SpriteBatch, stars: &[Star]) {
     for star in stars {
-        draw_texture_ex(
-            star_texture,
-            star.x,
-            star.y,
-            star.color,
-            DrawTextureParams {
-                rotation: star.rotation,
-                ..Default::default()
-            },
-        );
+        particles.push(Sprite::Star, star.x, star.y, star.rotation, star.color);
     }
+    particles.draw();
 }
 
 fn update_hearts_positions(hearts: &mut Vec<Heart>, heart_size: f32, window_size: (f32, f32)) {
@@ -94,22 +123,17 @@ fn draw_texture_at_x_y(
     y: f32,
     rotation: f32,
     y_offset: Option<f32>,
+    color: Color,
 ) {
-    let texture_y: f32;
-    match y_offset {
-        None => texture_y = y,
-        Some(offset) => texture_y = y + offset,
-    }
+    let texture_y = match y_offset {
+        None => y,
+        Some(offset) => y + offset,
+    };
     draw_texture_ex(
-        &image_texture,
+        image_texture,
         x,
         texture_y,
-        Color {
-            r: 1.0,
-            g: 1.0,
-            b: 1.0,
-            a: 1.0,
-        },
+        color,
         DrawTextureParams {
             rotation,
             ..Default::default()
@@ -117,91 +141,6 @@ fn draw_texture_at_x_y(
     );
 }
 
-fn draw_text_outline(
-    text: &str,
-    x: f32,
-    y: f32,
-    size: f32,
-    outline_offset: (f32, f32),
-    primary_color: Color,
-    outline_color: Color,
-    font: &Font,
-) {
-    draw_text_ex(
-        text,
-        x + outline_offset.0,
-        y + outline_offset.1,
-        TextParams {
-            font: Some(font),
-            font_size: size as u16,
-            color: outline_color,
-            ..Default::default()
-        },
-    );
-    draw_text_ex(
-        text,
-        x,
-        y,
-        TextParams {
-            font: Some(font),
-            font_size: size as u16,
-            color: primary_color,
-            ..Default::default()
-        },
-    );
-}
-
-fn draw_line_of_text_in_the_center(
-    text: &str,
-    font: &Font,
-    font_size: u16,
-    window_size: (f32, f32),
-    y_offset: f32,
-    first_color: Color,
-    second_color: Color,
-) -> f32 {
-    let font_scale = 1.0;
-    let text_size = measure_text(&text, Some(font), font_size, font_scale);
-
-    draw_text_outline(
-        text,
-        (window_size.0 - text_size.width) / 2.0,
-        y_offset,
-        font_size as f32,
-        (5.0, 5.0),
-        first_color,
-        second_color,
-        &font,
-    );
-
-    return text_size.height;
-}
-
-fn draw_multiline_text_in_the_center(
-    texts: &Vec<&str>,
-    font: &Font,
-    font_size: u16,
-    window_size: (f32, f32),
-    y_offset: f32,
-    lines_spacing: f32,
-    first_color: Color,
-    second_color: Color,
-) {
-    let mut line_offset = 0.0;
-    for text in texts {
-        let text_height = draw_line_of_text_in_the_center(
-            text,
-            &font,
-            font_size,
-            window_size,
-            y_offset + line_offset,
-            first_color,
-            second_color,
-        );
-        line_offset += text_height + lines_spacing;
-    }
-}
-
 fn recreate_texture_coords(
     window_size: (f32, f32),
     texture: &Texture2D,
@@ -209,10 +148,10 @@ fn recreate_texture_coords(
 ) -> (f32, f32) {
     let x = (window_size.0 - texture.width()) / 2.0;
     let y = (window_size.1 - texture.width()) / 2.0 + y_offset;
-    return (x, y);
+    (x, y)
 }
 
-fn recreate_hearts(window_size: (f32, f32), colors: &Vec<Color>) -> Vec<Heart> {
+fn recreate_hearts(window_size: (f32, f32), colors: &[Color]) -> Vec<Heart> {
     let mut hearts = vec![];
     for color in colors.iter() {
         let x = rand::gen_range(0.0, window_size.0);
@@ -224,10 +163,10 @@ fn recreate_hearts(window_size: (f32, f32), colors: &Vec<Color>) -> Vec<Heart> {
             color: *color,
         })
     }
-    return hearts;
+    hearts
 }
 
-fn recreate_stars(window_size: (f32, f32), colors: &Vec<Color>) -> Vec<Star> {
+fn recreate_stars(window_size: (f32, f32), colors: &[Color]) -> Vec<Star> {
     let mut stars = vec![];
     for color in colors.iter() {
         let x = rand::gen_range(0.0, window_size.0);
@@ -239,14 +178,7 @@ fn recreate_stars(window_size: (f32, f32), colors: &Vec<Color>) -> Vec<Star> {
             rotation: rand::gen_range(0.0, MAX_ROTATION),
         })
     }
-    return stars;
-}
-
-fn get_next_font_index(fonts: &Vec<Font>, current_index: usize) -> usize {
-    match fonts.get(current_index + 1) {
-        None => 0,
-        Some(_) => current_index + 1,
-    }
+    stars
 }
 
 fn update_star_positions(stars: &mut Vec<Star>, star_size: f32, window_size: (f32, f32)) {
@@ -274,506 +206,337 @@ fn update_star_positions(stars: &mut Vec<Star>, star_size: f32, window_size: (f3
     }
 }
 
-fn generate_colors(
-    amount: usize,
-    main_color_range: (f32, f32),
-    other_color_multiplier: f32,
-    rgb_distribution: (bool, bool, bool),
-) -> Vec<Color> {
-    let mut colors: Vec<Color> = vec![];
-    for _ in 0..amount {
-        let main_color =
-            rand::gen_range(main_color_range.0, main_color_range.1) / main_color_range.1;
-        let other_color =
-            rand::gen_range(0.0, main_color * other_color_multiplier) / main_color_range.1;
-
-        colors.push(Color {
-            r: if rgb_distribution.0 {
-                main_color
-            } else {
-                other_color
-            },
-            g: if rgb_distribution.1 {
-                main_color
-            } else {
-                other_color
-            },
-            b: if rgb_distribution.2 {
-                main_color
-            } else {
-                other_color
-            },
-            a: 1.0,
+async fn game() {
+    let settings = Settings::from_environment();
+    let story_source = match &settings.story_link {
+        Some(payload) => StorySource::Link(payload.clone()),
+        None => StorySource::File(
+            settings
+                .story_path
+                .clone()
+                .unwrap_or(STORY_PATH.to_string()),
+        ),
+    };
+    let Assets {
+        mut story,
+        fonts,
+        heart_texture,
+        star_texture,
+        mut particles,
+        mut scene_textures,
+        mut warnings,
+    } = match load_assets(&story_source, settings.start_scene.unwrap_or(0)).await {
+        Ok(assets) => assets,
+        Err(error) => loop {
+            draw_error_screen(&error);
+            next_frame().await
+        },
+    };
+    story.report_missing_translations();
+    if let Some(seed) = settings.seed {
+        rand::srand(seed);
+    }
+    let mut recorder = settings.render.as_ref().map(|render| {
+        Recorder::new(render).unwrap_or_else(|error| {
+            eprintln!("error: {error}");
+            std::process::exit(1);
         })
+    });
+    if recorder.is_some() {
+        clock::use_fixed_step(1.0 / SIMULATION_FPS as f32);
     }
-    return colors;
-}
 
-async fn game() {
-    let fonts = vec![
-        load_ttf_font("fonts/Swampy Clean.ttf").await.unwrap(),
-        load_ttf_font("fonts/MorfinSans-Regular.ttf").await.unwrap(),
-        load_ttf_font("fonts/Ramona-Bold.ttf").await.unwrap(),
-    ];
+    let mut language =
+        story.pick_language(&[settings.language.clone(), platform::system_language()]);
 
-    let mut font_index = 0;
+    let mut font_cycler = FontCycler::new();
     let mut window_size = screen_size();
     let rotation_step = 0.010;
 
     let y_offset = 300.0;
-    let star_image = load_image("images/star.png").await.unwrap();
-    let star_texture = Texture2D::from_image(&star_image);
-    let heart_image = load_image("images/heart.png").await.unwrap();
-    let heart_texture = Texture2D::from_image(&heart_image);
     let mut texture_rotation = 0.0;
 
     let texture_y_offset: Option<f32> = None;
 
-    let mut scene_index = 0;
-
-    let scenes = vec![
-        SceneConfig {
-            what_draw: WhatDraw::Hearts,
-            bg_color: Color {
-                r: 0.1,
-                g: 0.2,
-                b: 0.1,
-                a: 1.0,
-            },
-            texture: Texture2D::from_image(&load_image("images/flower.png").await.unwrap()),
-            texts: vec![
-                "Азалька!",
-                "я тебя очень",
-                "сильно люблю!",
-                "(жми на цветочек)",
-            ],
-            text_colors: (WHITE, BLACK),
-            colors: generate_colors(HEARTS_AMOUNT, (500.0, 1000.0), 200.0, (false, true, false)),
-        },
-        SceneConfig {
-            what_draw: WhatDraw::Stars,
-            bg_color: Color {
-                r: 1.0,
-                g: 1.0,
-                b: 0.9,
-                a: 1.0,
-            },
-            texture: Texture2D::from_image(&load_image("images/present.png").await.unwrap()),
-            texts: vec![
-                "У меня даже есть",
-                "для тебя!!!",
-                "подарок!!!",
-                "Но сначала я хочу",
-                "сказать тебе",
-                "какая ты ...",
-            ],
-            text_colors: (BLACK, WHITE),
-            colors: generate_colors(STARS_AMOUNT, (900.0, 1000.0), 500.0, (true, true, false)),
-        },
-        SceneConfig {
-            what_draw: WhatDraw::Hearts,
-            bg_color: Color {
-                r: 0.7,
-                g: 0.7,
-                b: 1.0,
-                a: 1.0,
-            },
-            texture: Texture2D::from_image(&load_image("images/flower-white.png").await.unwrap()),
-            texts: vec!["Замечательная!", "Умная! Красивая!", "Добрая! Милая!", ""],
-            text_colors: (WHITE, BLACK),
-            colors: generate_colors(HEARTS_AMOUNT, (700.0, 1000.0), 1000.0, (false, false, true)),
-        },
-        SceneConfig {
-            what_draw: WhatDraw::Stars,
-            bg_color: Color {
-                r: 0.7,
-                g: 1.0,
-                b: 1.0,
-                a: 1.0,
-            },
-            texture: Texture2D::from_image(&load_image("images/flower-blue.png").await.unwrap()),
-            texts: vec![
-                "Гениальная!",
-                "Душещипательная!",
-                "Восхитительная!",
-                "Страстная!",
-                "Матурка и матурымка!",
-            ],
-            text_colors: (WHITE, BLACK),
-            colors: generate_colors(STARS_AMOUNT, (500.0, 1000.0), 200.0, (false, true, true)),
-        },
-        SceneConfig {
-            what_draw: WhatDraw::Hearts,
-            bg_color: Color {
-                r: 1.0,
-                g: 0.7,
-                b: 0.65,
-                a: 1.0,
-            },
-            texture: Texture2D::from_image(&load_image("images/flower-red.png").await.unwrap()),
-
-            texts: vec![
-                "Я так рад,",
-                "что встретил тебя",
-                "и продолжаю",
-                "встречать",
-                "уже целых 2!!!",
-                "года!!!",
-            ],
-            text_colors: (WHITE, BLACK),
-            colors: generate_colors(HEARTS_AMOUNT, (700.0, 1000.0), 200.0, (true, false, false)),
-        },
-        SceneConfig {
-            what_draw: WhatDraw::Stars,
-            bg_color: Color {
-                r: 0.33,
-                g: 0.7,
-                b: 0.7,
-                a: 1.0,
-            },
-            texture: Texture2D::from_image(&load_image("images/sad.png").await.unwrap()),
-
-            texts: vec![
-                "Если бы не ты",
-                "я б так и был",
-                "холостым (фуууу)",
-                "и одиноким :(",
-            ],
-            text_colors: (BLACK, WHITE),
-            colors: generate_colors(HEARTS_AMOUNT, (850.0, 1000.0), 1000.0, (true, true, true)),
-        },
-        SceneConfig {
-            what_draw: WhatDraw::Hearts,
-            bg_color: Color {
-                r: 0.0,
-                g: 0.6,
-                b: 1.0,
-                a: 1.0,
-            },
-            texture: Texture2D::from_image(&load_image("images/sun.png").await.unwrap()),
-            texts: vec![
-                "ТЫ МОЕ СОЛНЫШКО!!!",
-                "я очень сильно",
-                "горжусь тобой!",
-                "И не устаю",
-                "удивляться судьбе",
-                "каждый день!!!",
-            ],
-            text_colors: (BLACK, WHITE),
-            colors: generate_colors(HEARTS_AMOUNT, (999.0, 1000.0), 500.0, (true, true, true)),
-        },
-        SceneConfig {
-            what_draw: WhatDraw::Hearts,
-            bg_color: Color {
-                r: 1.0,
-                g: 0.75,
-                b: 0.7,
-                a: 1.0,
-            },
-            texture: Texture2D::from_image(&load_image("images/us.png").await.unwrap()),
-            texts: vec!["А теперь...!"],
-            text_colors: (WHITE, BLACK),
-            colors: generate_colors(HEARTS_AMOUNT, (500.0, 1000.0), 100.0, (true, false, false)),
-        },
-        SceneConfig {
-            what_draw: WhatDraw::Hearts,
-            bg_color: Color {
-                r: 1.0,
-                g: 0.7,
-                b: 0.65,
-                a: 1.0,
-            },
-            texture: Texture2D::from_image(&load_image("images/old.png").await.unwrap()),
-            texts: vec![
-                "Давай посмотрим",
-                "немного на нас!",
-                "вот наша фотка",
-                "с еще совсем",
-                "давних времен:",
-            ],
-            text_colors: (WHITE, BLACK),
-            colors: generate_colors(HEARTS_AMOUNT, (500.0, 1000.0), 100.0, (true, false, false)),
-        },
-        SceneConfig {
-            what_draw: WhatDraw::Hearts,
-            bg_color: Color {
-                r: 1.0,
-                g: 0.65,
-                b: 0.6,
-                a: 1.0,
-            },
-            texture: Texture2D::from_image(&load_image("images/old2.png").await.unwrap()),
-            texts: vec![
-                "Или вот,",
-                "как мы ехали",
-                "в автобусе, а у",
-                "у меня еще была",
-                "эта дурацкая",
-                "прическа xD",
-            ],
-            text_colors: (WHITE, BLACK),
-            colors: generate_colors(HEARTS_AMOUNT, (500.0, 1000.0), 100.0, (true, false, false)),
-        },
-        SceneConfig {
-            what_draw: WhatDraw::Hearts,
-            bg_color: Color {
-                r: 1.0,
-                g: 0.6,
-                b: 0.55,
-                a: 1.0,
-            },
-            texture: Texture2D::from_image(&load_image("images/old-fav.png").await.unwrap()),
-            texts: vec![
-                "А вот этой",
-                "фоткой я",
-                "очееееень долго",
-                "любовался!",
-                "для нее у меня",
-                "специальное место",
-                "в сердечке <3",
-            ],
-            text_colors: (WHITE, BLACK),
-            colors: generate_colors(HEARTS_AMOUNT, (500.0, 1000.0), 100.0, (true, false, false)),
-        },
-        SceneConfig {
-            what_draw: WhatDraw::Hearts,
-            bg_color: Color {
-                r: 1.0,
-                g: 0.55,
-                b: 0.5,
-                a: 1.0,
-            },
-            texture: Texture2D::from_image(&load_image("images/crazy3.png").await.unwrap()),
-            texts: vec!["Но знаешь, что", "мне нравится", "больше всего?.."],
-            text_colors: (WHITE, BLACK),
-            colors: generate_colors(HEARTS_AMOUNT, (500.0, 1000.0), 100.0, (true, false, false)),
-        },
-        SceneConfig {
-            what_draw: WhatDraw::Hearts,
-            bg_color: Color {
-                r: 1.0,
-                g: 0.45,
-                b: 0.4,
-                a: 1.0,
-            },
-            texture: Texture2D::from_image(&load_image("images/crazy1.png").await.unwrap()),
-            texts: vec!["Что мы..."],
-            text_colors: (WHITE, BLACK),
-            colors: generate_colors(HEARTS_AMOUNT, (500.0, 1000.0), 100.0, (true, false, false)),
-        },
-        SceneConfig {
-            what_draw: WhatDraw::Hearts,
-            bg_color: Color {
-                r: 1.0,
-                g: 0.4,
-                b: 0.35,
-                a: 1.0,
-            },
-            texture: Texture2D::from_image(&load_image("images/crazy2.png").await.unwrap()),
-            texts: vec!["C самого", "первого дня..."],
-            text_colors: (WHITE, BLACK),
-            colors: generate_colors(HEARTS_AMOUNT, (500.0, 1000.0), 100.0, (true, false, false)),
-        },
-        SceneConfig {
-            what_draw: WhatDraw::Hearts,
-            bg_color: Color {
-                r: 1.0,
-                g: 0.35,
-                b: 0.3,
-                a: 1.0,
-            },
-            texture: Texture2D::from_image(&load_image("images/new.png").await.unwrap()),
-            texts: vec!["Не перестаем", "быть крейзиками!"],
-            text_colors: (WHITE, BLACK),
-            colors: generate_colors(HEARTS_AMOUNT, (500.0, 1000.0), 100.0, (true, false, false)),
-        },
-        SceneConfig {
-            what_draw: WhatDraw::Hearts,
-            bg_color: Color {
-                r: 1.0,
-                g: 0.3,
-                b: 0.25,
-                a: 1.0,
-            },
-            texture: Texture2D::from_image(&load_image("images/crazy4.png").await.unwrap()),
-            texts: vec![
-                "И хоть это и",
-                "не единствнное",
-                "за что я тебя",
-                "люблю...",
-            ],
-            text_colors: (WHITE, BLACK),
-            colors: generate_colors(HEARTS_AMOUNT, (500.0, 1000.0), 100.0, (true, false, false)),
-        },
-        SceneConfig {
-            what_draw: WhatDraw::Hearts,
-            bg_color: Color {
-                r: 1.0,
-                g: 0.25,
-                b: 0.2,
-                a: 1.0,
-            },
-            texture: Texture2D::from_image(&load_image("images/crazy5.png").await.unwrap()),
-            texts: vec![
-                "Но лишь эта одна",
-                "НАША черта",
-                "определенно стоит",
-                "тысячей миллионов",
-                "триллионов",
-                "денег!!!",
-            ],
-            text_colors: (WHITE, BLACK),
-            colors: generate_colors(HEARTS_AMOUNT, (500.0, 1000.0), 100.0, (true, false, false)),
-        },
-        SceneConfig {
-            what_draw: WhatDraw::Hearts,
-            bg_color: Color {
-                r: 1.0,
-                g: 0.2,
-                b: 0.15,
-                a: 1.0,
-            },
-            texture: Texture2D::from_image(&load_image("images/lovers1.png").await.unwrap()),
-            texts: vec![
-                "Которые я",
-                "ни за что и никогда",
-                "НЕ ПРИМУ",
-                "потому для меня",
-                "это БЕСЦЕННО.",
-            ],
-            text_colors: (WHITE, BLACK),
-            colors: generate_colors(HEARTS_AMOUNT, (500.0, 1000.0), 100.0, (true, false, false)),
-        },
-        SceneConfig {
-            what_draw: WhatDraw::Hearts,
-            bg_color: Color {
-                r: 1.0,
-                g: 0.15,
-                b: 0.1,
-                a: 1.0,
-            },
-            texture: Texture2D::from_image(&load_image("images/lovers2.png").await.unwrap()),
-            texts: vec![
-                "Я очень тебя люблю,",
-                "моя матурымка",
-                "и если бы мне дали",
-                "выбор - я бы",
-                "ничего не менял,",
-                "потому что жизнь с",
-                "тобой - это то, о чем",
-                "я даже и не думал,",
-                "что мечтал!!!",
-            ],
-            text_colors: (WHITE, BLACK),
-            colors: generate_colors(HEARTS_AMOUNT, (500.0, 1000.0), 100.0, (true, false, false)),
-        },
-        SceneConfig {
-            what_draw: WhatDraw::Hearts,
-            bg_color: Color {
-                r: 1.0,
-                g: 0.15,
-                b: 0.1,
-                a: 1.0,
-            },
-            texture: Texture2D::from_image(&load_image("images/empty.png").await.unwrap()),
-            texts: vec![
-                "ЛЮБЛЮ",
-                "ЛЮБЛЮ",
-                "ЛЮБЛЮ",
-                "ЛЮБЛЮ",
-                "ЛЮБЛЮ",
-                "ЛЮБЛЮ",
-                "ЛЮБЛЮ",
-                "ЛЮБЛЮ",
-                "ЛЮБЛЮ",
-                "ЛЮБЛЮ",
-                "ЛЮБЛЮ",
-                "ЛЮБЛЮ",
-                "ЛЮБЛЮ",
-                "ЛЮБЛЮ",
-                "ЛЮБЛЮ",
-                "ЛЮБЛЮ",
-                "ЛЮБЛЮ",
-            ],
-            text_colors: (WHITE, BLACK),
-            colors: generate_colors(HEARTS_AMOUNT, (800.0, 1000.0), 100.0, (true, false, false)),
-        },
-    ];
-
-    let mut hearts: Vec<Heart> = recreate_hearts(window_size, &scenes[scene_index].colors);
-    let mut stars: Vec<Star> = recreate_stars(window_size, &scenes[scene_index].colors);
+    let mut scene_index = settings.start_scene.unwrap_or(0);
+    let mut pending_scene: Option<usize> = None;
+    // a recording moves through the scenes on its own
+    let mut slideshow = Slideshow::new(settings.autoplay || recorder.is_some());
+    let mut scene_started = clock::time();
+
+    let mut scene_colors: Vec<Vec<Color>> = story
+        .scenes
+        .iter()
+        .map(|scene| scene.palette.generate())
+        .collect();
+
+    report_missing_glyphs(&story, &fonts);
+
+    let mut visited = vec![false; story.scenes.len()];
+    visited[scene_index] = true;
+    let mut overview_open = false;
+    let mut show_progress = settings.show_progress;
+    let mut editor = Editor::new(&story);
+    let mut debug_overlay = DebugOverlay::new(settings.debug);
+    let mut screenshot = Screenshot::new(settings.screenshot_scale);
+    let mut ending = Ending::new();
+    let mut scroller = TextScroller::new();
+    let handwriting_clip = HandwritingClip::new();
+
+    let mut hearts: Vec<Heart> = recreate_hearts(window_size, &scene_colors[scene_index]);
+    let mut stars: Vec<Star> = recreate_stars(window_size, &scene_colors[scene_index]);
+
+    let mut rich_texts =
+        parse_texts(story.scenes[scene_index].texts_for(&language, &story.default_language));
 
     let (mut current_x, mut current_y) =
-        recreate_texture_coords(window_size, &scenes[scene_index].texture, y_offset);
+        recreate_texture_coords(window_size, scene_textures.texture(scene_index), y_offset);
 
     loop {
+        if let Some(recorder) = &mut recorder {
+            clock::advance();
+            screenshot.requested = recorder.next_frame();
+        }
+        scene_textures.update(
+            scene_index,
+            pending_scene.unwrap_or(scene_index),
+            &mut warnings,
+        );
+
+        // the next scene is shown only once its texture is decoded
+        if let Some(next_index) = pending_scene.filter(|index| scene_textures.is_ready(*index)) {
+            pending_scene = None;
+            scene_index = next_index;
+            visited[scene_index] = true;
+            ending.open = false;
+            let new_scene = &story.scenes[scene_index];
+            rich_texts = parse_texts(new_scene.texts_for(&language, &story.default_language));
+            slideshow.restart();
+            scroller.restart();
+            scene_started = clock::time();
+            (current_x, current_y) =
+                recreate_texture_coords(window_size, scene_textures.texture(scene_index), y_offset);
+
+            match new_scene.what_draw {
+                WhatDraw::Hearts => {
+                    hearts = recreate_hearts(window_size, &scene_colors[scene_index]);
+                }
+                WhatDraw::Stars => {
+                    stars = recreate_stars(window_size, &scene_colors[scene_index]);
+                }
+            }
+        }
+
         let (mouse_x, mouse_y) = mouse_position();
         let current_window_size = screen_size();
 
-        let current_scene = &scenes[scene_index];
+        let current_scene = &story.scenes[scene_index];
+        let current_texture = scene_textures.texture(scene_index);
 
-        clear_background(current_scene.bg_color);
+        // while editing, keys go to the text fields, and a recording plays untouched
+        let shortcuts = !editor.open && recorder.is_none();
+        if shortcuts && is_key_pressed(KeyCode::L) {
+            language = story.next_language(&language);
+            rich_texts = parse_texts(current_scene.texts_for(&language, &story.default_language));
+            scroller.restart();
+        }
+
+        let scene_time = (clock::time() - scene_started) as f32;
+        let animations = &current_scene.animations;
+        let background_pose = sample(animations.background.as_ref(), scene_time);
+        let texture_pose = sample(animations.texture.as_ref(), scene_time);
+        let text_pose = sample(animations.text.as_ref(), scene_time);
+        let window_center = vec2(window_size.0, window_size.1) / 2.0;
+
+        screenshot.begin(window_size);
+        let bg_color = tint(current_scene.bg_color, background_pose.color);
+        clear_background(bg_color);
+        background_pose.draw_around(window_center, || {
+            draw_layers(&current_scene.layers, (mouse_x, mouse_y), window_size)
+        });
+        // fading the layers out is drawing the plain background back over them
+        if background_pose.opacity < 1.0 {
+            draw_rectangle(
+                0.0,
+                0.0,
+                window_size.0,
+                window_size.1,
+                Color {
+                    a: 1.0 - background_pose.opacity.clamp(0.0, 1.0),
+                    ..bg_color
+                },
+            );
+        }
+
+        if shortcuts && is_key_pressed(KeyCode::A) {
+            slideshow.autoplay = !slideshow.autoplay;
+            slideshow.restart();
+        }
+        if shortcuts && is_key_pressed(KeyCode::Space) {
+            slideshow.paused = !slideshow.paused;
+        }
+        if shortcuts && is_key_pressed(KeyCode::P) {
+            show_progress = !show_progress;
+        }
+        if shortcuts && is_key_pressed(KeyCode::S) {
+            screenshot.requested = true;
+        }
+        if (shortcuts && is_key_pressed(KeyCode::Tab)) || is_overview_button_clicked(window_size) {
+            overview_open = !overview_open;
+        } else if is_key_pressed(KeyCode::Escape) {
+            overview_open = false;
+        }
 
-        if (mouse_x > current_x)
-            & (mouse_y > current_y)
-            & (mouse_x < current_x + current_scene.texture.width())
-            & (mouse_y < current_y + current_scene.texture.height())
-            & is_mouse_button_pressed(MouseButton::Left)
-        {
+        let (texture_x, texture_y) = (
+            current_x + texture_pose.offset.x,
+            current_y + texture_pose.offset.y,
+        );
+        let hovered = recorder.is_none()
+            & (mouse_x > texture_x)
+            & (mouse_y > texture_y)
+            & (mouse_x < texture_x + current_texture.width())
+            & (mouse_y < texture_y + current_texture.height());
+        let text_fonts = TextFonts {
+            regular: &fonts[font_cycler.index],
+            bold: &fonts[BOLD_FONT_INDEX],
+            fallbacks: current_scene.text_style.fallback_fonts(&fonts),
+        };
+        // handwritten texts add the time the pen takes
+        let writing_duration = current_scene
+            .text_style
+            .handwriting
+            .as_ref()
+            .filter(|_| current_scene.scroll.is_none())
+            .map_or(0.0, |handwriting| {
+                handwriting.duration(&line_widths(&rich_texts, &text_fonts, TEXT_SIZE))
+            });
+        let scene_duration = recorder
+            .as_ref()
+            .and_then(|recorder| recorder.scene_seconds)
+            .or(current_scene.duration)
+            .unwrap_or_else(|| default_duration(&rich_texts) + writing_duration);
+        // the scene behind the overview, the editor or the ending waits and ignores clicks
+        let covered = overview_open || editor.open || ending.open;
+        let scene_over =
+            pending_scene.is_none() && slideshow.update(scene_duration, hovered || covered);
+
+        let clicked = hovered
+            & !covered
+            & match current_scene.scroll {
+                Some(_) => scroller.is_click(),
+                None => is_mouse_button_pressed(MouseButton::Left),
+            };
+        if clicked {
+            ending.picture_clicks += 1;
+        }
+        if clicked | scene_over {
             let next_index = scene_index + 1;
-            if next_index < scenes.len() {
-                scene_index += 1;
-                let new_scene = &scenes[scene_index];
-                (current_x, current_y) =
-                    recreate_texture_coords(window_size, &new_scene.texture, y_offset);
-
-                match new_scene.what_draw {
-                    WhatDraw::Hearts => {
-                        hearts = recreate_hearts(window_size, &new_scene.colors);
-                    }
-                    WhatDraw::Stars => {
-                        stars = recreate_stars(window_size, &new_scene.colors);
+            if next_index < story.scenes.len() {
+                pending_scene = Some(next_index);
+            } else {
+                ending.show(&story, &language, &visited);
+            }
+        }
+        if scene_over && scene_index + 1 == story.scenes.len() {
+            if let Some(recorder) = recorder.take() {
+                match recorder.finish() {
+                    Ok(message) => println!("{message}"),
+                    Err(error) => {
+                        eprintln!("error: {error}");
+                        std::process::exit(1);
                     }
                 }
+                return;
             }
         }
 
         if current_window_size != window_size {
             window_size = current_window_size;
             (current_x, current_y) =
-                recreate_texture_coords(window_size, &current_scene.texture, y_offset);
-            hearts = recreate_hearts(window_size, &current_scene.colors);
-            stars = recreate_stars(window_size, &current_scene.colors);
+                recreate_texture_coords(window_size, current_texture, y_offset);
+            hearts = recreate_hearts(window_size, &scene_colors[scene_index]);
+            stars = recreate_stars(window_size, &scene_colors[scene_index]);
         }
 
         match &current_scene.what_draw {
             WhatDraw::Stars => {
-                draw_stars(&star_texture, &stars);
+                draw_stars(&mut particles, &stars);
                 update_star_positions(&mut stars, star_texture.width(), window_size);
             }
             WhatDraw::Hearts => {
-                draw_hearts(&heart_texture, &hearts);
-                update_hearts_positions(&mut hearts, heart_image.width as f32, window_size);
+                draw_hearts(&mut particles, &hearts);
+                update_hearts_positions(&mut hearts, heart_texture.width(), window_size);
             }
         }
 
-        draw_texture_at_x_y(
-            &current_scene.texture,
-            current_x,
-            current_y,
-            texture_rotation,
-            texture_y_offset,
-        );
-        draw_multiline_text_in_the_center(
-            &current_scene.texts,
-            fonts.get(font_index).unwrap(),
-            TEXT_SIZE,
-            window_size,
-            y_offset,
-            LINES_SPACING,
-            current_scene.text_colors.0,
-            current_scene.text_colors.1,
-        );
+        let texture_center = vec2(current_x, current_y) + current_texture.size() / 2.0;
+        texture_pose.draw_around(texture_center, || {
+            draw_texture_at_x_y(
+                current_texture,
+                current_x,
+                current_y,
+                texture_rotation,
+                texture_y_offset,
+                texture_pose.tint(),
+            );
+            slideshow.draw_ring(
+                texture_center,
+                current_texture.size().length() / 2.0,
+                scene_duration,
+                hovered,
+            );
+        });
+        let icons = InlineIcons {
+            heart: &heart_texture,
+            star: &star_texture,
+        };
+        let text_colors = TextColors {
+            primary: current_scene.text_colors.0,
+            secondary: current_scene.text_colors.1,
+            tint: text_pose.tint(),
+        };
+        text_pose.draw_around(window_center, || match &current_scene.scroll {
+            Some(scroll) => scroller.draw(
+                &rich_texts,
+                &text_fonts,
+                &icons,
+                window_size,
+                scroll,
+                &current_scene.text_style,
+                text_colors,
+                shortcuts && !covered,
+            ),
+            None => {
+                draw_text_written(
+                    &rich_texts,
+                    &text_fonts,
+                    &icons,
+                    TEXT_SIZE,
+                    window_size,
+                    &current_scene.text_style,
+                    text_colors,
+                    Writing {
+                        clip: &handwriting_clip,
+                        elapsed: scene_time,
+                    },
+                );
+            }
+        });
+
+        // the progress row, buttons and overlays stay out of the picture
+        match (screenshot.finish(window_size), &mut recorder) {
+            (Some(image), Some(recorder)) => {
+                if let Err(error) = recorder.add_frame(&image) {
+                    eprintln!("error: {error}");
+                    std::process::exit(1);
+                }
+            }
+            (Some(image), None) => match screenshot::save(&image, scene_index) {
+                Ok(message) => info!("{}", message),
+                Err(error) => warnings.push(format!("screenshot failed: {error}")),
+            },
+            (None, _) => {}
+        }
 
         if texture_rotation < MAX_ROTATION {
             texture_rotation += rotation_step;
@@ -781,8 +544,103 @@ async fn game() {
             texture_rotation = 0.0;
         }
 
-        if rand::gen_range(0, 100) > 97 {
-            font_index = get_next_font_index(&fonts, font_index);
+        if show_progress {
+            draw_progress(scene_index, story.scenes.len(), window_size);
+        }
+        if ending.open && !overview_open {
+            match ending.draw(&text_fonts, &icons, window_size) {
+                Some(EndingAction::Replay) => {
+                    ending.restart();
+                    visited = vec![false; story.scenes.len()];
+                    pending_scene = Some(0);
+                }
+                Some(EndingAction::Revisit) => overview_open = true,
+                None => {}
+            }
+        }
+        draw_overview_button(window_size);
+        if overview_open {
+            if let Some(index) = draw_overview(&scene_textures, &visited, scene_index, window_size)
+            {
+                overview_open = false;
+                if index != scene_index {
+                    pending_scene = Some(index);
+                }
+            }
+        }
+
+        draw_warnings(&warnings);
+
+        debug_overlay.record_frame();
+        if is_key_pressed(KeyCode::F3) {
+            debug_overlay.open = !debug_overlay.open;
+        }
+        if debug_overlay.open {
+            debug_overlay.draw(&DebugInfo {
+                particles: match current_scene.what_draw {
+                    WhatDraw::Hearts => hearts.len(),
+                    WhatDraw::Stars => stars.len(),
+                },
+                scene_index,
+                scenes_amount: story.scenes.len(),
+                scene_id: &current_scene.texture,
+                font_index: font_cycler.index,
+                window_size,
+                texture_size: current_texture.size(),
+                texture_memory: scene_textures.memory()
+                    + texture_bytes(heart_texture.size())
+                    + texture_bytes(star_texture.size())
+                    + texture_bytes(particles.atlas_size()),
+            });
+        }
+
+        font_cycler.update(&current_scene.text_style.font, fonts.len());
+
+        if is_key_pressed(KeyCode::F2) {
+            editor.open = !editor.open;
+        }
+        if editor.open {
+            let changes = editor.draw(&mut story, scene_index, &language, &story_source);
+            if changes.screenshot {
+                screenshot.requested = true;
+            }
+            // everything indexed by scene is built again for the new order,
+            // scene_index may point past the end after a removal until then
+            if let Some(index) = changes.scenes {
+                scene_textures = SceneTextures::new(
+                    story
+                        .scenes
+                        .iter()
+                        .map(|scene| scene.texture.clone())
+                        .collect(),
+                );
+                scene_colors = story
+                    .scenes
+                    .iter()
+                    .map(|scene| scene.palette.generate())
+                    .collect();
+                visited = vec![false; story.scenes.len()];
+                scene_index = index;
+                visited[scene_index] = true;
+                pending_scene = Some(scene_index);
+            } else {
+                let scene = &story.scenes[scene_index];
+                if changes.texts {
+                    rich_texts = parse_texts(scene.texts_for(&language, &story.default_language));
+                }
+                if changes.palette {
+                    scene_colors[scene_index] = scene.palette.generate();
+                    hearts = recreate_hearts(window_size, &scene_colors[scene_index]);
+                    stars = recreate_stars(window_size, &scene_colors[scene_index]);
+                }
+                if changes.texture {
+                    scene_textures.set_path(scene_index, scene.texture.clone());
+                    pending_scene = Some(scene_index);
+                }
+                if let Some(index) = changes.jump {
+                    pending_scene = Some(index);
+                }
+            }
         }
 
         next_frame().await
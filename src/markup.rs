@@ -0,0 +1,310 @@
+use macroquad::prelude::*;
+
+// Inline markup for scene texts, e.g.
+// "я тебя [color=red]очень[/color] [b]сильно[/b] люблю [icon=heart]"
+//
+// [color=red] / [color=#ff8080]  per-word color
+// [b]                             alternate (bold) font
+// [size=1.5]                      size multiplier, 0.25 to 4 in total
+// [shake] / [wave]                animated letters
+// [icon=heart] / [icon=star]      inline sprite, no closing tag
+//
+// Unknown or malformed tags are kept as plain text.
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum Effect {
+    None,
+    Shake,
+    Wave,
+}
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum Icon {
+    Heart,
+    Star,
+}
+
+#[derive(Clone, Copy)]
+pub struct SpanStyle {
+    pub color: Option<Color>,
+    pub bold: bool,
+    pub scale: f32,
+    pub effect: Effect,
+}
+
+pub enum Span {
+    Text { text: String, style: SpanStyle },
+    Icon { icon: Icon, style: SpanStyle },
+}
+
+pub struct RichLine {
+    pub spans: Vec<Span>,
+}
+
+impl Default for SpanStyle {
+    fn default() -> Self {
+        SpanStyle {
+            color: None,
+            bold: false,
+            scale: 1.0,
+            effect: Effect::None,
+        }
+    }
+}
+
+// what nested [size] tags may add up to, glyphs far bigger would not fit in memory
+const SCALE_RANGE: std::ops::RangeInclusive<f32> = 0.25..=4.0;
+
+const NAMED_COLORS: [(&str, Color); 14] = [
+    ("white", WHITE),
+    ("black", BLACK),
+    ("red", RED),
+    ("pink", PINK),
+    ("gold", GOLD),
+    ("yellow", YELLOW),
+    ("orange", ORANGE),
+    ("green", GREEN),
+    ("blue", BLUE),
+    ("skyblue", SKYBLUE),
+    ("purple", PURPLE),
+    ("violet", VIOLET),
+    ("magenta", MAGENTA),
+    ("gray", GRAY),
+];
+
+pub fn parse_color(value: &str) -> Option<Color> {
+    if let Some(hex) = value.strip_prefix('#') {
+        let channel = |i: usize| u8::from_str_radix(hex.get(i..i + 2)?, 16).ok();
+        return match hex.len() {
+            6 => Some(Color::from_rgba(channel(0)?, channel(2)?, channel(4)?, 255)),
+            8 => Some(Color::from_rgba(
+                channel(0)?,
+                channel(2)?,
+                channel(4)?,
+                channel(6)?,
+            )),
+            _ => None,
+        };
+    }
+
+    let value = value.to_lowercase();
+    NAMED_COLORS
+        .iter()
+        .find(|(name, _)| *name == value)
+        .map(|(_, color)| *color)
+}
+
+// the name a color is written back with, when it has one
+pub fn color_name(color: Color) -> Option<&'static str> {
+    NAMED_COLORS
+        .iter()
+        .find(|(_, named)| *named == color)
+        .map(|(name, _)| *name)
+}
+
+fn parse_icon(value: &str) -> Option<Icon> {
+    match value {
+        "heart" => Some(Icon::Heart),
+        "star" => Some(Icon::Star),
+        _ => None,
+    }
+}
+
+fn push_text(spans: &mut Vec<Span>, text: &str, style: SpanStyle) {
+    if text.is_empty() {
+        return;
+    }
+    spans.push(Span::Text {
+        text: text.to_string(),
+        style,
+    });
+}
+
+// returns false when the tag is not recognized so it can be printed as is
+fn apply_tag(tag: &str, stack: &mut Vec<(String, SpanStyle)>, spans: &mut Vec<Span>) -> bool {
+    let current = stack.last().map(|(_, style)| *style).unwrap_or_default();
+
+    if let Some(name) = tag.strip_prefix('/') {
+        return match stack.last() {
+            Some((open, _)) if open == name => {
+                stack.pop();
+                true
+            }
+            _ => false,
+        };
+    }
+
+    let (name, value) = match tag.split_once('=') {
+        Some((name, value)) => (name, Some(value)),
+        None => (tag, None),
+    };
+
+    let mut style = current;
+    match (name, value) {
+        ("icon", Some(value)) => {
+            return match parse_icon(value) {
+                Some(icon) => {
+                    spans.push(Span::Icon {
+                        icon,
+                        style: current,
+                    });
+                    true
+                }
+                None => false,
+            };
+        }
+        ("color", Some(value)) => match parse_color(value) {
+            Some(color) => style.color = Some(color),
+            None => return false,
+        },
+        ("size", Some(value)) => match value.parse::<f32>() {
+            Ok(scale) if SCALE_RANGE.contains(&(style.scale * scale)) => style.scale *= scale,
+            _ => return false,
+        },
+        ("b", None) => style.bold = true,
+        ("shake", None) => style.effect = Effect::Shake,
+        ("wave", None) => style.effect = Effect::Wave,
+        _ => return false,
+    }
+
+    stack.push((name.to_string(), style));
+    true
+}
+
+pub fn parse_line(line: &str) -> RichLine {
+    let mut spans = vec![];
+    let mut stack: Vec<(String, SpanStyle)> = vec![];
+    let mut rest = line;
+
+    while let Some(start) = rest.find('[') {
+        let style = stack.last().map(|(_, style)| *style).unwrap_or_default();
+        let Some(length) = rest[start..].find(']') else {
+            break;
+        };
+        let end = start + length;
+
+        push_text(&mut spans, &rest[..start], style);
+        if !apply_tag(&rest[start + 1..end], &mut stack, &mut spans) {
+            push_text(&mut spans, &rest[start..=end], style);
+        }
+        rest = &rest[end + 1..];
+    }
+
+    let style = stack.last().map(|(_, style)| *style).unwrap_or_default();
+    push_text(&mut spans, rest, style);
+
+    RichLine { spans }
+}
+
+pub fn parse_texts(texts: &[String]) -> Vec<RichLine> {
+    texts.iter().map(|text| parse_line(text)).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // the text of every span with whether it is bold, icons as "<icon>"
+    fn pieces(line: &str) -> Vec<(String, bool)> {
+        parse_line(line)
+            .spans
+            .into_iter()
+            .map(|span| match span {
+                Span::Text { text, style } => (text, style.bold),
+                Span::Icon { style, .. } => ("<icon>".to_string(), style.bold),
+            })
+            .collect()
+    }
+
+    fn piece(text: &str, bold: bool) -> (String, bool) {
+        (text.to_string(), bold)
+    }
+
+    #[test]
+    fn plain_text_is_one_span() {
+        assert_eq!(pieces("в сердечке <3"), vec![piece("в сердечке <3", false)]);
+    }
+
+    #[test]
+    fn tags_style_what_they_enclose() {
+        assert_eq!(
+            pieces("я [b]очень[/b] люблю [icon=heart]"),
+            vec![
+                piece("я ", false),
+                piece("очень", true),
+                piece(" люблю ", false),
+                piece("<icon>", false),
+            ]
+        );
+    }
+
+    #[test]
+    fn unclosed_tag_lasts_until_the_end() {
+        assert_eq!(
+            pieces("a [b]bold to the end"),
+            vec![piece("a ", false), piece("bold to the end", true)]
+        );
+    }
+
+    #[test]
+    fn mismatched_closing_tag_is_kept_as_text() {
+        assert_eq!(
+            pieces("[b]x[/wave]y[/b]z"),
+            vec![
+                piece("x", true),
+                piece("[/wave]", true),
+                piece("y", true),
+                piece("z", false),
+            ]
+        );
+    }
+
+    #[test]
+    fn unknown_and_broken_tags_are_kept_as_text() {
+        assert_eq!(
+            pieces("[icon=moon][size=-1][color=nope]"),
+            vec![
+                piece("[icon=moon]", false),
+                piece("[size=-1]", false),
+                piece("[color=nope]", false),
+            ]
+        );
+        assert_eq!(pieces("no [end"), vec![piece("no [end", false)]);
+    }
+
+    #[test]
+    fn colors_by_name_and_hex() {
+        assert_eq!(parse_color("Red"), Some(RED));
+        assert_eq!(
+            parse_color("#ff000080"),
+            Some(Color::from_rgba(255, 0, 0, 128))
+        );
+        assert_eq!(parse_color("#ff00"), None);
+        assert_eq!(parse_color("#гг0000"), None);
+    }
+
+    #[test]
+    fn sizes_outside_the_range_are_kept_as_text() {
+        assert_eq!(
+            pieces("[size=inf]a[size=nan]b[size=0]c"),
+            vec![
+                piece("[size=inf]", false),
+                piece("a", false),
+                piece("[size=nan]", false),
+                piece("b", false),
+                piece("[size=0]", false),
+                piece("c", false),
+            ]
+        );
+        // nested sizes multiply, the tag that goes past the limit is refused
+        let line = parse_line("[size=2][size=2]x[size=2]y");
+        let scales: Vec<f32> = line
+            .spans
+            .iter()
+            .map(|span| match span {
+                Span::Text { style, .. } | Span::Icon { style, .. } => style.scale,
+            })
+            .collect();
+        assert_eq!(scales, vec![4.0, 4.0, 4.0]);
+    }
+}
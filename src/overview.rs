@@ -0,0 +1,159 @@
+use macroquad::prelude::*;
+
+use crate::assets::{draw_heart_shape, SceneTextures};
+
+const PROGRESS_MAX_HEART: f32 = 24.0;
+const PROGRESS_MARGIN: f32 = 12.0;
+const BUTTON_SIZE: f32 = 44.0;
+const CELL_PADDING: f32 = 12.0;
+
+pub fn draw_progress(scene_index: usize, scenes_amount: usize, window_size: (f32, f32)) {
+    let size = (window_size.0 / (scenes_amount as f32 * 1.5)).min(PROGRESS_MAX_HEART);
+    let row_width = size * 1.5 * scenes_amount as f32 - size * 0.5;
+    let y = window_size.1 - size - PROGRESS_MARGIN;
+    for index in 0..scenes_amount {
+        let x = (window_size.0 - row_width) / 2.0 + index as f32 * size * 1.5;
+        let color = if index <= scene_index {
+            Color::new(0.9, 0.1, 0.2, 0.9)
+        } else {
+            Color::new(1.0, 1.0, 1.0, 0.5)
+        };
+        draw_heart_shape(x, y, size, color);
+    }
+}
+
+fn button_rect(window_size: (f32, f32)) -> Rect {
+    Rect::new(
+        window_size.0 - BUTTON_SIZE - PROGRESS_MARGIN,
+        PROGRESS_MARGIN,
+        BUTTON_SIZE,
+        BUTTON_SIZE,
+    )
+}
+
+// the corner button that opens the overview, three lines like a menu icon
+pub fn draw_overview_button(window_size: (f32, f32)) {
+    let rect = button_rect(window_size);
+    draw_rectangle(
+        rect.x,
+        rect.y,
+        rect.w,
+        rect.h,
+        Color::new(0.0, 0.0, 0.0, 0.3),
+    );
+    for line in 1..=3 {
+        let y = rect.y + rect.h * line as f32 / 4.0;
+        draw_line(
+            rect.x + rect.w * 0.2,
+            y,
+            rect.x + rect.w * 0.8,
+            y,
+            3.0,
+            WHITE,
+        );
+    }
+}
+
+pub fn is_overview_button_clicked(window_size: (f32, f32)) -> bool {
+    is_mouse_button_pressed(MouseButton::Left)
+        && button_rect(window_size).contains(mouse_position().into())
+}
+
+fn cell_rect(index: usize, scenes_amount: usize, window_size: (f32, f32)) -> Rect {
+    let columns = (scenes_amount as f32).sqrt().ceil().max(1.0) as usize;
+    let rows = scenes_amount.div_ceil(columns);
+    let cell = (window_size.0 / columns as f32).min(window_size.1 / rows as f32);
+    let left = (window_size.0 - cell * columns as f32) / 2.0;
+    let top = (window_size.1 - cell * rows as f32) / 2.0;
+    Rect::new(
+        left + (index % columns) as f32 * cell + CELL_PADDING,
+        top + (index / columns) as f32 * cell + CELL_PADDING,
+        cell - CELL_PADDING * 2.0,
+        cell - CELL_PADDING * 2.0,
+    )
+}
+
+fn draw_cell(
+    index: usize,
+    rect: Rect,
+    thumbnail: Option<&Texture2D>,
+    visited: bool,
+    current: bool,
+) {
+    let background = if visited {
+        Color::new(1.0, 1.0, 1.0, 0.15)
+    } else {
+        Color::new(0.0, 0.0, 0.0, 0.4)
+    };
+    draw_rectangle(rect.x, rect.y, rect.w, rect.h, background);
+
+    match thumbnail.filter(|_| visited) {
+        Some(texture) => {
+            let scale = (rect.w / texture.width()).min(rect.h / texture.height());
+            let size = texture.size() * scale;
+            draw_texture_ex(
+                texture,
+                rect.x + (rect.w - size.x) / 2.0,
+                rect.y + (rect.h - size.y) / 2.0,
+                WHITE,
+                DrawTextureParams {
+                    dest_size: Some(size),
+                    ..Default::default()
+                },
+            );
+        }
+        None => draw_heart_shape(
+            rect.x + rect.w * 0.3,
+            rect.y + rect.h * 0.3,
+            rect.w * 0.4,
+            Color::new(1.0, 1.0, 1.0, 0.2),
+        ),
+    }
+
+    if current {
+        draw_rectangle_lines(rect.x, rect.y, rect.w, rect.h, 4.0, PINK);
+    }
+    draw_text(
+        &(index + 1).to_string(),
+        rect.x + 6.0,
+        rect.y + rect.h - 8.0,
+        24.0,
+        WHITE,
+    );
+}
+
+// Thumbnails of the scenes already seen, the rest stay hidden.
+// Returns the visited scene that was clicked.
+pub fn draw_overview(
+    textures: &SceneTextures,
+    visited: &[bool],
+    scene_index: usize,
+    window_size: (f32, f32),
+) -> Option<usize> {
+    draw_rectangle(
+        0.0,
+        0.0,
+        window_size.0,
+        window_size.1,
+        Color::new(0.1, 0.02, 0.05, 0.85),
+    );
+
+    let mut clicked = None;
+    for (index, &seen) in visited.iter().enumerate() {
+        let rect = cell_rect(index, visited.len(), window_size);
+        draw_cell(
+            index,
+            rect,
+            textures.thumbnail(index),
+            seen,
+            index == scene_index,
+        );
+        if seen
+            && is_mouse_button_pressed(MouseButton::Left)
+            && rect.contains(mouse_position().into())
+        {
+            clicked = Some(index);
+        }
+    }
+    clicked
+}
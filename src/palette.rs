@@ -0,0 +1,260 @@
+use macroquad::{prelude::*, rand};
+use serde::{Deserialize, Serialize};
+
+use crate::story::color_format;
+
+// more would not fit the frame time, every story file is checked against it
+pub const MAX_PARTICLES: usize = 2000;
+
+// How many particles a scene has and how they are colored.
+// The colors part is told apart by its fields:
+//   {"main_color_range": .., "other_color_multiplier": .., "rgb_distribution": ..}
+//   {"hue": [330, 360], "saturation": [0.4, 0.9], "value": [0.8, 1.0]}
+//   {"preset": "ocean"}
+//   {"colors": ["#ff0000", "pink"]}
+//   {"gradient": ["gold", "#ff6080"]}
+#[derive(Clone, PartialEq, Serialize, Deserialize)]
+pub struct Palette {
+    pub amount: usize,
+    #[serde(flatten)]
+    pub colors: PaletteColors,
+}
+
+#[derive(Clone, PartialEq, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum PaletteColors {
+    // one random main channel strength per particle, the channels outside the mask get a share of it
+    Mask {
+        main_color_range: (f32, f32),
+        other_color_multiplier: f32,
+        rgb_distribution: (bool, bool, bool),
+    },
+    // random colors inside the ranges, hue in degrees and allowed to wrap past 360
+    Hsv {
+        hue: (f32, f32),
+        saturation: (f32, f32),
+        value: (f32, f32),
+    },
+    Preset {
+        preset: PalettePreset,
+    },
+    // particles take the colors in turn
+    List {
+        #[serde(with = "color_format::list")]
+        colors: Vec<Color>,
+    },
+    // particles are spread evenly from the first color to the last
+    Gradient {
+        #[serde(with = "color_format::list")]
+        gradient: Vec<Color>,
+    },
+}
+
+#[derive(Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PalettePreset {
+    Rose,
+    Sunset,
+    Ocean,
+    Gold,
+    PastelRainbow,
+}
+
+pub const PRESETS: [PalettePreset; 5] = [
+    PalettePreset::Rose,
+    PalettePreset::Sunset,
+    PalettePreset::Ocean,
+    PalettePreset::Gold,
+    PalettePreset::PastelRainbow,
+];
+
+impl PalettePreset {
+    pub fn name(self) -> &'static str {
+        match self {
+            PalettePreset::Rose => "rose",
+            PalettePreset::Sunset => "sunset",
+            PalettePreset::Ocean => "ocean",
+            PalettePreset::Gold => "gold",
+            PalettePreset::PastelRainbow => "pastel rainbow",
+        }
+    }
+
+    pub fn colors(self) -> PaletteColors {
+        let (hue, saturation, value) = match self {
+            PalettePreset::Rose => ((330.0, 360.0), (0.35, 0.85), (0.85, 1.0)),
+            PalettePreset::Sunset => ((-20.0, 45.0), (0.7, 1.0), (0.9, 1.0)),
+            PalettePreset::Ocean => ((180.0, 230.0), (0.5, 1.0), (0.5, 1.0)),
+            PalettePreset::Gold => ((38.0, 52.0), (0.6, 1.0), (0.8, 1.0)),
+            PalettePreset::PastelRainbow => ((0.0, 360.0), (0.25, 0.45), (0.95, 1.0)),
+        };
+        PaletteColors::Hsv {
+            hue,
+            saturation,
+            value,
+        }
+    }
+}
+
+pub fn hsv_to_rgb(hue: f32, saturation: f32, value: f32) -> Color {
+    let hue = hue.rem_euclid(360.0) / 60.0;
+    let chroma = value * saturation;
+    let x = chroma * (1.0 - (hue % 2.0 - 1.0).abs());
+    let (r, g, b) = match hue as u32 {
+        0 => (chroma, x, 0.0),
+        1 => (x, chroma, 0.0),
+        2 => (0.0, chroma, x),
+        3 => (0.0, x, chroma),
+        4 => (x, 0.0, chroma),
+        _ => (chroma, 0.0, x),
+    };
+    let m = value - chroma;
+    Color::new(r + m, g + m, b + m, 1.0)
+}
+
+fn random_in((from, to): (f32, f32)) -> f32 {
+    if from < to {
+        rand::gen_range(from, to)
+    } else {
+        from
+    }
+}
+
+fn sample_gradient(stops: &[Color], t: f32) -> Color {
+    match stops {
+        [] => WHITE,
+        [only] => *only,
+        _ => {
+            let position = t.clamp(0.0, 1.0) * (stops.len() - 1) as f32;
+            let index = (position as usize).min(stops.len() - 2);
+            let local = position - index as f32;
+            let (from, to) = (stops[index], stops[index + 1]);
+            Color::new(
+                from.r + (to.r - from.r) * local,
+                from.g + (to.g - from.g) * local,
+                from.b + (to.b - from.b) * local,
+                from.a + (to.a - from.a) * local,
+            )
+        }
+    }
+}
+
+pub fn generate_colors(
+    amount: usize,
+    main_color_range: (f32, f32),
+    other_color_multiplier: f32,
+    rgb_distribution: (bool, bool, bool),
+) -> Vec<Color> {
+    let mut colors: Vec<Color> = vec![];
+    for _ in 0..amount {
+        let main_color =
+            rand::gen_range(main_color_range.0, main_color_range.1) / main_color_range.1;
+        let other_color =
+            rand::gen_range(0.0, main_color * other_color_multiplier) / main_color_range.1;
+
+        colors.push(Color {
+            r: if rgb_distribution.0 {
+                main_color
+            } else {
+                other_color
+            },
+            g: if rgb_distribution.1 {
+                main_color
+            } else {
+                other_color
+            },
+            b: if rgb_distribution.2 {
+                main_color
+            } else {
+                other_color
+            },
+            a: 1.0,
+        })
+    }
+    colors
+}
+
+impl Palette {
+    pub fn generate(&self) -> Vec<Color> {
+        match &self.colors {
+            PaletteColors::Mask {
+                main_color_range,
+                other_color_multiplier,
+                rgb_distribution,
+            } => generate_colors(
+                self.amount,
+                *main_color_range,
+                *other_color_multiplier,
+                *rgb_distribution,
+            ),
+            PaletteColors::Hsv {
+                hue,
+                saturation,
+                value,
+            } => (0..self.amount)
+                .map(|_| hsv_to_rgb(random_in(*hue), random_in(*saturation), random_in(*value)))
+                .collect(),
+            PaletteColors::Preset { preset } => Palette {
+                amount: self.amount,
+                colors: preset.colors(),
+            }
+            .generate(),
+            PaletteColors::List { colors } if !colors.is_empty() => (0..self.amount)
+                .map(|index| colors[index % colors.len()])
+                .collect(),
+            PaletteColors::List { .. } => vec![WHITE; self.amount],
+            PaletteColors::Gradient { gradient } => (0..self.amount)
+                .map(|index| {
+                    sample_gradient(
+                        gradient,
+                        index as f32 / self.amount.saturating_sub(1).max(1) as f32,
+                    )
+                })
+                .collect(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn palette(json: &str) -> Palette {
+        serde_json::from_str(json).unwrap()
+    }
+
+    fn rgb(color: Color) -> [u8; 3] {
+        [color.r, color.g, color.b].map(|channel| (channel * 255.0).round() as u8)
+    }
+
+    #[test]
+    fn hues_wrap_around() {
+        assert_eq!(rgb(hsv_to_rgb(0.0, 1.0, 1.0)), [255, 0, 0]);
+        assert_eq!(rgb(hsv_to_rgb(360.0, 1.0, 1.0)), [255, 0, 0]);
+        assert_eq!(rgb(hsv_to_rgb(-60.0, 1.0, 1.0)), [255, 0, 255]);
+        assert_eq!(rgb(hsv_to_rgb(-380.0, 1.0, 1.0)), [255, 0, 85]);
+        assert_eq!(rgb(hsv_to_rgb(420.0, 1.0, 1.0)), [255, 255, 0]);
+        assert_eq!(rgb(hsv_to_rgb(200.0, 0.0, 0.5)), [128, 128, 128]);
+    }
+
+    #[test]
+    fn gradients_with_few_stops() {
+        assert_eq!(sample_gradient(&[], 0.5), WHITE);
+        assert_eq!(sample_gradient(&[GOLD], 0.7), GOLD);
+        let halfway = sample_gradient(&[BLACK, WHITE], 0.5);
+        assert_eq!(rgb(halfway), [128, 128, 128]);
+        assert_eq!(sample_gradient(&[BLACK, RED, WHITE], 2.0), WHITE);
+    }
+
+    #[test]
+    fn a_single_particle_takes_the_first_color() {
+        let gradient = palette(r#"{"amount": 1, "gradient": ["red", "blue"]}"#);
+        assert_eq!(gradient.generate(), vec![RED]);
+        let list = palette(r#"{"amount": 3, "colors": ["red", "blue"]}"#);
+        assert_eq!(list.generate(), vec![RED, BLUE, RED]);
+        let empty = palette(r#"{"amount": 2, "colors": []}"#);
+        assert_eq!(empty.generate(), vec![WHITE; 2]);
+        let spread = palette(r#"{"amount": 3, "gradient": ["black", "white"]}"#).generate();
+        assert_eq!(rgb(spread[1]), [128, 128, 128]);
+        assert_eq!(spread[2], WHITE);
+    }
+}
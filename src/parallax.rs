@@ -0,0 +1,156 @@
+use macroquad::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::clock;
+use crate::story::color_format;
+
+const SKY_STRIPES: usize = 32;
+const CLOUD_SPACING: f32 = 320.0;
+const HILL_SPACING: f32 = 260.0;
+const FLOWER_SPACING: f32 = 70.0;
+
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum LayerKind {
+    Sky {
+        #[serde(with = "color_format")]
+        top_color: Color,
+    },
+    Clouds,
+    Hills,
+    Flowers {
+        #[serde(with = "color_format")]
+        center_color: Color,
+    },
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+pub struct BackgroundLayer {
+    #[serde(flatten)]
+    pub kind: LayerKind,
+    #[serde(with = "color_format")]
+    pub color: Color,
+    // horizontal drift in pixels per second
+    pub scroll_speed: f32,
+    // how many pixels the layer moves when the mouse reaches the window edge
+    pub mouse_factor: f32,
+    // where the layer sits, as a fraction of the window height from the top
+    pub height: f32,
+}
+
+// cheap deterministic noise so every tile keeps its shape while scrolling
+fn tile_noise(tile: i32, seed: u32) -> f32 {
+    let mut n = (tile as u32).wrapping_mul(374_761_393) ^ seed.wrapping_mul(668_265_263);
+    n = (n ^ (n >> 13)).wrapping_mul(1_274_126_177);
+    n ^= n >> 16;
+    (n % 10_000) as f32 / 10_000.0
+}
+
+fn mouse_shift(mouse_factor: f32, mouse: (f32, f32), window_size: (f32, f32)) -> (f32, f32) {
+    let dx = (mouse.0 / window_size.0 - 0.5).clamp(-0.5, 0.5) * 2.0;
+    let dy = (mouse.1 / window_size.1 - 0.5).clamp(-0.5, 0.5) * 2.0;
+    (-dx * mouse_factor, -dy * mouse_factor)
+}
+
+fn visible_tiles(scroll: f32, spacing: f32, window_width: f32) -> std::ops::RangeInclusive<i32> {
+    let first = ((-scroll) / spacing).floor() as i32 - 1;
+    let last = ((window_width - scroll) / spacing).ceil() as i32 + 1;
+    first..=last
+}
+
+fn draw_sky(layer: &BackgroundLayer, top_color: Color, window_size: (f32, f32)) {
+    let bottom = window_size.1 * layer.height;
+    let stripe_height = bottom / SKY_STRIPES as f32;
+    for i in 0..SKY_STRIPES {
+        let t = i as f32 / (SKY_STRIPES - 1) as f32;
+        let color = Color {
+            r: top_color.r + (layer.color.r - top_color.r) * t,
+            g: top_color.g + (layer.color.g - top_color.g) * t,
+            b: top_color.b + (layer.color.b - top_color.b) * t,
+            a: top_color.a + (layer.color.a - top_color.a) * t,
+        };
+        draw_rectangle(
+            0.0,
+            i as f32 * stripe_height,
+            window_size.0,
+            stripe_height + 1.0,
+            color,
+        );
+    }
+}
+
+fn draw_clouds(layer: &BackgroundLayer, scroll: f32, shift: (f32, f32), window_size: (f32, f32)) {
+    let base_y = window_size.1 * layer.height + shift.1;
+    for tile in visible_tiles(scroll, CLOUD_SPACING, window_size.0) {
+        let x = tile as f32 * CLOUD_SPACING + scroll;
+        let y = base_y + (tile_noise(tile, 1) - 0.5) * 120.0;
+        let size = 30.0 + tile_noise(tile, 2) * 30.0;
+        draw_circle(x, y, size, layer.color);
+        draw_circle(x + size * 0.9, y - size * 0.4, size * 1.2, layer.color);
+        draw_circle(x + size * 2.0, y, size * 0.9, layer.color);
+        draw_rectangle(x, y - size * 0.2, size * 2.0, size * 1.1, layer.color);
+    }
+}
+
+fn draw_hills(layer: &BackgroundLayer, scroll: f32, shift: (f32, f32), window_size: (f32, f32)) {
+    let top = window_size.1 * layer.height + shift.1;
+    for tile in visible_tiles(scroll, HILL_SPACING, window_size.0) {
+        let x = tile as f32 * HILL_SPACING + scroll;
+        let radius = HILL_SPACING * (0.7 + tile_noise(tile, 3) * 0.5);
+        draw_circle(x, top + radius * 0.8, radius, layer.color);
+    }
+    draw_rectangle(
+        0.0,
+        top + HILL_SPACING * 0.5,
+        window_size.0,
+        window_size.1,
+        layer.color,
+    );
+}
+
+fn draw_flowers(
+    layer: &BackgroundLayer,
+    center_color: Color,
+    scroll: f32,
+    shift: (f32, f32),
+    window_size: (f32, f32),
+) {
+    let base_y = window_size.1 * layer.height + shift.1;
+    let sway = clock::time() as f32 * 2.0;
+    for tile in visible_tiles(scroll, FLOWER_SPACING, window_size.0) {
+        let x = tile as f32 * FLOWER_SPACING + scroll + tile_noise(tile, 4) * 30.0;
+        let y = base_y + tile_noise(tile, 5) * 40.0;
+        let size = 6.0 + tile_noise(tile, 6) * 6.0;
+        let head_x = x + (sway + tile as f32).sin() * 3.0;
+        let head_y = y - size * 4.0;
+
+        draw_line(x, window_size.1, head_x, head_y, 3.0, DARKGREEN);
+        for petal in 0..5 {
+            let angle = petal as f32 / 5.0 * std::f32::consts::TAU;
+            draw_circle(
+                head_x + angle.cos() * size,
+                head_y + angle.sin() * size,
+                size * 0.8,
+                layer.color,
+            );
+        }
+        draw_circle(head_x, head_y, size * 0.6, center_color);
+    }
+}
+
+pub fn draw_layers(layers: &[BackgroundLayer], mouse: (f32, f32), window_size: (f32, f32)) {
+    let time = clock::time() as f32;
+    for layer in layers {
+        let shift = mouse_shift(layer.mouse_factor, mouse, window_size);
+        let scroll = time * layer.scroll_speed + shift.0;
+
+        match &layer.kind {
+            LayerKind::Sky { top_color } => draw_sky(layer, *top_color, window_size),
+            LayerKind::Clouds => draw_clouds(layer, scroll, shift, window_size),
+            LayerKind::Hills => draw_hills(layer, scroll, shift, window_size),
+            LayerKind::Flowers { center_color } => {
+                draw_flowers(layer, *center_color, scroll, shift, window_size)
+            }
+        }
+    }
+}
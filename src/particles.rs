@@ -0,0 +1,118 @@
+use macroquad::{models::Vertex, prelude::*};
+
+// macroquad clamps a single draw call to 5000 indices, 6 of which make a sprite
+const MAX_BATCH_SPRITES: usize = 5000 / 6 - 1;
+// transparent border between packed sprites so filtering does not bleed them together
+const ATLAS_PADDING: u16 = 2;
+
+#[derive(Clone, Copy)]
+pub enum Sprite {
+    Heart,
+    Star,
+}
+
+// Every particle sprite packed in one texture and drawn as one mesh per frame,
+// instead of a draw_texture call per particle.
+pub struct SpriteBatch {
+    // pixel rects of the sprites in the atlas, in Sprite order
+    regions: Vec<Rect>,
+    atlas_size: Vec2,
+    mesh: Mesh,
+}
+
+fn copy_image(target: &mut Image, source: &Image, x: u16) {
+    let target_width = target.width as usize;
+    let target_data = target.get_image_data_mut();
+    for (row, pixels) in source
+        .get_image_data()
+        .chunks(source.width as usize)
+        .enumerate()
+    {
+        let start = (row + ATLAS_PADDING as usize) * target_width + x as usize;
+        target_data[start..start + pixels.len()].copy_from_slice(pixels);
+    }
+}
+
+impl SpriteBatch {
+    // the images are given in Sprite order and packed in a single row
+    pub fn new(images: &[&Image]) -> Self {
+        let width = images
+            .iter()
+            .map(|image| image.width + ATLAS_PADDING)
+            .sum::<u16>()
+            + ATLAS_PADDING;
+        let height = images.iter().map(|image| image.height).max().unwrap_or(0) + ATLAS_PADDING * 2;
+
+        let mut atlas = Image::gen_image_color(width, height, BLANK);
+        let mut regions = vec![];
+        let mut x = ATLAS_PADDING;
+        for image in images {
+            copy_image(&mut atlas, image, x);
+            regions.push(Rect::new(
+                x as f32,
+                ATLAS_PADDING as f32,
+                image.width as f32,
+                image.height as f32,
+            ));
+            x += image.width + ATLAS_PADDING;
+        }
+
+        SpriteBatch {
+            regions,
+            atlas_size: vec2(width as f32, height as f32),
+            mesh: Mesh {
+                vertices: vec![],
+                indices: vec![],
+                texture: Some(Texture2D::from_image(&atlas)),
+            },
+        }
+    }
+
+    // same placement as draw_texture_ex: top left corner at x, y and rotation around the center
+    pub fn push(&mut self, sprite: Sprite, x: f32, y: f32, rotation: f32, color: Color) {
+        if self.mesh.indices.len() / 6 >= MAX_BATCH_SPRITES {
+            self.draw();
+        }
+
+        let region = self.regions[sprite as usize];
+        let half = vec2(region.w, region.h) / 2.0;
+        let center = vec2(x, y) + half;
+        let (sin, cos) = rotation.sin_cos();
+        let corners = [
+            (vec2(-half.x, -half.y), vec2(region.left(), region.top())),
+            (vec2(half.x, -half.y), vec2(region.right(), region.top())),
+            (vec2(half.x, half.y), vec2(region.right(), region.bottom())),
+            (vec2(-half.x, half.y), vec2(region.left(), region.bottom())),
+        ];
+
+        let first = self.mesh.vertices.len() as u16;
+        for (offset, uv) in corners {
+            let position = center
+                + vec2(
+                    offset.x * cos - offset.y * sin,
+                    offset.x * sin + offset.y * cos,
+                );
+            self.mesh.vertices.push(Vertex {
+                position: vec3(position.x, position.y, 0.0),
+                uv: uv / self.atlas_size,
+                color,
+            });
+        }
+        self.mesh
+            .indices
+            .extend([0, 1, 2, 0, 2, 3].map(|index| first + index));
+    }
+
+    pub fn atlas_size(&self) -> Vec2 {
+        self.atlas_size
+    }
+
+    // draws everything pushed since the last call
+    pub fn draw(&mut self) {
+        if !self.mesh.indices.is_empty() {
+            draw_mesh(&self.mesh);
+        }
+        self.mesh.vertices.clear();
+        self.mesh.indices.clear();
+    }
+}
@@ -0,0 +1,125 @@
+// Things the desktop and the web builds get from different places.
+// The web side is implemented in web.js, which index.html loads after gl.js.
+
+#[cfg(target_arch = "wasm32")]
+mod web {
+    const BUFFER_SIZE: usize = 1024;
+
+    extern "C" {
+        fn anniversary_browser_language(buffer: *mut u8, max_length: u32) -> u32;
+        // returns -1 when the parameter is not in the page url
+        fn anniversary_query_param(
+            name: *const u8,
+            name_length: u32,
+            buffer: *mut u8,
+            max_length: u32,
+        ) -> i32;
+        // both return -1 when the page url has no fragment
+        fn anniversary_url_fragment_length() -> i32;
+        fn anniversary_url_fragment(buffer: *mut u8, max_length: u32) -> i32;
+        fn anniversary_download(
+            name: *const u8,
+            name_length: u32,
+            mime_type: *const u8,
+            mime_type_length: u32,
+            data: *const u8,
+            data_length: u32,
+        );
+    }
+
+    // lets gl.js check that web.js matches the crate
+    #[no_mangle]
+    pub extern "C" fn anniversary_crate_version() -> u32 {
+        let major: u32 = env!("CARGO_PKG_VERSION_MAJOR").parse().unwrap();
+        let minor: u32 = env!("CARGO_PKG_VERSION_MINOR").parse().unwrap();
+        let patch: u32 = env!("CARGO_PKG_VERSION_PATCH").parse().unwrap();
+        (major << 24) + (minor << 16) + patch
+    }
+
+    fn read_buffer(buffer: &[u8], length: usize) -> Option<String> {
+        String::from_utf8(buffer[..length.min(BUFFER_SIZE)].to_vec()).ok()
+    }
+
+    pub fn browser_language() -> Option<String> {
+        let mut buffer = [0u8; BUFFER_SIZE];
+        let length =
+            unsafe { anniversary_browser_language(buffer.as_mut_ptr(), BUFFER_SIZE as u32) };
+        read_buffer(&buffer, length as usize).filter(|language| !language.is_empty())
+    }
+
+    pub fn query_param(name: &str) -> Option<String> {
+        let mut buffer = [0u8; BUFFER_SIZE];
+        let length = unsafe {
+            anniversary_query_param(
+                name.as_ptr(),
+                name.len() as u32,
+                buffer.as_mut_ptr(),
+                BUFFER_SIZE as u32,
+            )
+        };
+        if length < 0 {
+            return None;
+        }
+        read_buffer(&buffer, length as usize)
+    }
+
+    // can be much longer than the other strings, so it gets a buffer of its own size
+    pub fn url_fragment() -> Option<String> {
+        let length = unsafe { anniversary_url_fragment_length() };
+        if length < 0 {
+            return None;
+        }
+        let mut buffer = vec![0u8; length as usize];
+        let written = unsafe { anniversary_url_fragment(buffer.as_mut_ptr(), length as u32) };
+        buffer.truncate(written.max(0) as usize);
+        String::from_utf8(buffer).ok()
+    }
+
+    pub fn download(name: &str, mime_type: &str, data: &[u8]) {
+        unsafe {
+            anniversary_download(
+                name.as_ptr(),
+                name.len() as u32,
+                mime_type.as_ptr(),
+                mime_type.len() as u32,
+                data.as_ptr(),
+                data.len() as u32,
+            )
+        }
+    }
+}
+
+#[cfg(target_arch = "wasm32")]
+pub fn system_language() -> Option<String> {
+    web::browser_language()
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+pub fn system_language() -> Option<String> {
+    sys_locale::get_locale()
+}
+
+#[cfg(target_arch = "wasm32")]
+pub fn query_param(name: &str) -> Option<String> {
+    web::query_param(name)
+}
+
+// the part of the page url after "#"
+#[cfg(target_arch = "wasm32")]
+pub fn url_fragment() -> Option<String> {
+    web::url_fragment()
+}
+
+// offered as a download in the browser, the mime type like "image/png" tells it what the file is
+#[cfg(target_arch = "wasm32")]
+pub fn save_file(name: &str, mime_type: &str, data: &[u8]) -> Result<String, String> {
+    web::download(name, mime_type, data);
+    Ok(format!("downloading {name}"))
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+pub fn save_file(name: &str, _mime_type: &str, data: &[u8]) -> Result<String, String> {
+    std::fs::write(name, data)
+        .map(|_| format!("saved {name}"))
+        .map_err(|error| format!("{name}: {error}"))
+}
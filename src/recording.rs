@@ -0,0 +1,188 @@
+// Plays the story through once at a fixed time step and writes what it shows,
+// see --render in cli.rs.
+
+use std::fs::File;
+use std::io::BufWriter;
+use std::path::{Path, PathBuf};
+
+use image::codecs::gif::{GifEncoder, Repeat};
+use image::error::{ImageError, ImageResult, ParameterError, ParameterErrorKind};
+use image::{Delay, Frame, RgbaImage};
+use macroquad::prelude::Image;
+
+use crate::screenshot::encode_png;
+use crate::settings::RenderSettings;
+
+// the particles and the texture move a fixed amount every frame, tuned for 60 fps
+pub const SIMULATION_FPS: u32 = 60;
+// 1 picks the gif palettes best and slowest, 30 the fastest
+const GIF_SPEED: i32 = 10;
+
+enum Output {
+    // numbered png files in a directory
+    Frames(PathBuf),
+    // created with the first frame, every frame gets its own palette
+    Gif(PathBuf, Option<GifEncoder<BufWriter<File>>>),
+}
+
+fn add_gif_frame(
+    encoder: &mut Option<GifEncoder<BufWriter<File>>>,
+    path: &Path,
+    image: &Image,
+    fps: u32,
+) -> ImageResult<()> {
+    if encoder.is_none() {
+        let file = BufWriter::new(File::create(path)?);
+        let mut gif = GifEncoder::new_with_speed(file, GIF_SPEED);
+        // the animation loops forever, like the story does in autoplay
+        gif.set_repeat(Repeat::Infinite)?;
+        *encoder = Some(gif);
+    }
+    let frame = RgbaImage::from_raw(image.width as u32, image.height as u32, image.bytes.clone())
+        .ok_or_else(|| {
+        ImageError::Parameter(ParameterError::from_kind(
+            ParameterErrorKind::DimensionMismatch,
+        ))
+    })?;
+    let delay = Delay::from_numer_denom_ms(1000, fps);
+    match encoder {
+        Some(encoder) => encoder.encode_frame(Frame::from_parts(frame, 0, 0, delay)),
+        None => Ok(()),
+    }
+}
+
+pub struct Recorder {
+    output: Output,
+    fps: u32,
+    // seconds every scene is recorded for instead of its own duration
+    pub scene_seconds: Option<f32>,
+    simulated_frames: u32,
+    written_frames: u32,
+}
+
+impl Recorder {
+    pub fn new(settings: &RenderSettings) -> Result<Self, String> {
+        let path = PathBuf::from(&settings.path);
+        let is_gif = path
+            .extension()
+            .is_some_and(|extension| extension.eq_ignore_ascii_case("gif"));
+        let output = if is_gif {
+            Output::Gif(path, None)
+        } else {
+            std::fs::create_dir_all(&path)
+                .map_err(|error| format!("{}: {error}", path.display()))?;
+            Output::Frames(path)
+        };
+        Ok(Recorder {
+            output,
+            fps: settings.fps,
+            scene_seconds: settings.scene_seconds,
+            simulated_frames: 0,
+            written_frames: 0,
+        })
+    }
+
+    // called once per simulated frame, true when this one goes into the recording
+    pub fn next_frame(&mut self) -> bool {
+        let wanted = self
+            .simulated_frames
+            .is_multiple_of(SIMULATION_FPS / self.fps);
+        self.simulated_frames += 1;
+        wanted
+    }
+
+    pub fn add_frame(&mut self, image: &Image) -> Result<(), String> {
+        self.written_frames += 1;
+        match &mut self.output {
+            Output::Frames(directory) => {
+                let path = directory.join(format!("frame-{:05}.png", self.written_frames));
+                encode_png(image).and_then(|png| {
+                    std::fs::write(&path, png)
+                        .map_err(|error| format!("{}: {error}", path.display()))
+                })
+            }
+            Output::Gif(path, encoder) => add_gif_frame(encoder, path, image, self.fps)
+                .map_err(|error| format!("{}: {error}", path.display())),
+        }
+    }
+
+    // what was written, for the message printed at the end
+    pub fn finish(self) -> Result<String, String> {
+        match self.output {
+            Output::Frames(directory) => Ok(format!(
+                "wrote {} frames to {}",
+                self.written_frames,
+                directory.display()
+            )),
+            Output::Gif(path, encoder) => {
+                // dropping the encoder writes the end of the gif and flushes the file
+                drop(encoder);
+                Ok(format!(
+                    "wrote {} frames to {}",
+                    self.written_frames,
+                    path.display()
+                ))
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use image::codecs::gif::GifDecoder;
+    use image::AnimationDecoder;
+
+    use super::*;
+
+    fn frame(width: u16, height: u16, color: [u8; 4]) -> Image {
+        Image {
+            bytes: color.repeat(width as usize * height as usize),
+            width,
+            height,
+        }
+    }
+
+    #[test]
+    fn gif_frames_decode_back() {
+        let path = std::env::temp_dir().join(format!("recording-{}.gif", std::process::id()));
+        let mut recorder = Recorder::new(&RenderSettings {
+            path: path.to_string_lossy().to_string(),
+            fps: 20,
+            scene_seconds: None,
+        })
+        .unwrap();
+        let colors = [[255, 0, 0, 255], [0, 255, 0, 255], [0, 0, 255, 255]];
+        for color in colors {
+            recorder.add_frame(&frame(8, 6, color)).unwrap();
+        }
+        recorder.finish().unwrap();
+
+        let file = std::io::BufReader::new(File::open(&path).unwrap());
+        let frames = GifDecoder::new(file)
+            .unwrap()
+            .into_frames()
+            .collect_frames()
+            .unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(frames.len(), colors.len());
+        for (frame, color) in frames.iter().zip(colors) {
+            assert_eq!(frame.buffer().dimensions(), (8, 6));
+            assert_eq!(frame.buffer().get_pixel(3, 3).0, color);
+            assert_eq!(frame.delay().numer_denom_ms(), (50, 1));
+        }
+    }
+
+    #[test]
+    fn only_every_nth_simulated_frame_is_recorded() {
+        let directory = std::env::temp_dir().join(format!("recording-{}", std::process::id()));
+        let mut recorder = Recorder::new(&RenderSettings {
+            path: directory.to_string_lossy().to_string(),
+            fps: 20,
+            scene_seconds: None,
+        })
+        .unwrap();
+        let recorded: Vec<bool> = (0..6).map(|_| recorder.next_frame()).collect();
+        std::fs::remove_dir(&directory).unwrap();
+        assert_eq!(recorded, [true, false, false, true, false, false]);
+    }
+}
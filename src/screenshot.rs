@@ -0,0 +1,94 @@
+use image::{codecs::png::PngEncoder, ColorType, ImageEncoder};
+use macroquad::{miniquad::date, prelude::*};
+
+use crate::platform;
+
+// larger render targets fail on many phones
+const MAX_SIZE: f32 = 4096.0;
+
+// Draws one frame offscreen, optionally larger than the window, and hands it over.
+// Screenshots are saved as png, see `save`; recording the story uses it too.
+pub struct Screenshot {
+    // how many times the window size the picture is
+    pub scale: f32,
+    // taken at the start of the next frame
+    pub requested: bool,
+    target: Option<RenderTarget>,
+}
+
+pub fn encode_png(image: &Image) -> Result<Vec<u8>, String> {
+    let mut png = vec![];
+    PngEncoder::new(&mut png)
+        .write_image(
+            &image.bytes,
+            image.width as u32,
+            image.height as u32,
+            ColorType::Rgba8,
+        )
+        .map_err(|error| error.to_string())?;
+    Ok(png)
+}
+
+// a file next to the game on desktop, a download in the browser
+pub fn save(image: &Image, scene_index: usize) -> Result<String, String> {
+    let name = format!("scene-{}-{}.png", scene_index + 1, date::now() as u64);
+    encode_png(image).and_then(|png| platform::save_file(&name, "image/png", &png))
+}
+
+impl Screenshot {
+    pub fn new(scale: f32) -> Self {
+        Screenshot {
+            scale,
+            requested: false,
+            target: None,
+        }
+    }
+
+    fn size(&self, window_size: (f32, f32)) -> (u32, u32) {
+        let scale = self
+            .scale
+            .min(MAX_SIZE / window_size.0.max(window_size.1))
+            .max(1.0);
+        (
+            (window_size.0 * scale).round() as u32,
+            (window_size.1 * scale).round() as u32,
+        )
+    }
+
+    // everything drawn until `finish` goes offscreen when a screenshot was asked for
+    pub fn begin(&mut self, window_size: (f32, f32)) {
+        if !std::mem::take(&mut self.requested) {
+            return;
+        }
+        let (width, height) = self.size(window_size);
+        let target = render_target(width, height);
+        target.texture.set_filter(FilterMode::Linear);
+        set_camera(&Camera2D {
+            render_target: Some(target.clone()),
+            ..Camera2D::from_display_rect(Rect::new(0.0, 0.0, window_size.0, window_size.1))
+        });
+        self.target = Some(target);
+    }
+
+    // the picture, also put in the window since the frame was not drawn there
+    pub fn finish(&mut self, window_size: (f32, f32)) -> Option<Image> {
+        let target = self.target.take()?;
+        set_default_camera();
+        draw_texture_ex(
+            &target.texture,
+            0.0,
+            0.0,
+            WHITE,
+            DrawTextureParams {
+                dest_size: Some(vec2(window_size.0, window_size.1)),
+                flip_y: true,
+                ..Default::default()
+            },
+        );
+        let mut image = target.texture.get_texture_data();
+        // pixels come back from the gpu bottom row first
+        let row = image.width as usize * 4;
+        image.bytes = image.bytes.chunks(row).rev().flatten().copied().collect();
+        Some(image)
+    }
+}
@@ -0,0 +1,183 @@
+use macroquad::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::clock;
+use crate::markup::RichLine;
+use crate::text::{
+    draw_text_block, line_heights, InlineIcons, TextColors, TextFonts, TextStyle, VerticalAnchor,
+};
+
+// pixels moved by a wheel notch and an arrow key press
+const WHEEL_STEP: f32 = 60.0;
+const KEY_STEP: f32 = 40.0;
+// auto scrolling picks up again this long after the last scroll by hand
+const HAND_PAUSE: f64 = 2.0;
+// a press that moved less than this many pixels is still a click
+const CLICK_SLOP: f32 = 6.0;
+
+// A scene whose texts are a long column that scrolls between two margins
+// instead of a block placed by the text style.
+#[derive(Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ScrollText {
+    // pixels per second when nobody scrolls by hand, 0 scrolls only by hand
+    pub speed: f32,
+    // where the column starts and ends, from the top and the bottom of the window
+    pub top: f32,
+    pub bottom: f32,
+    // height of the bands at both ends where lines fade out
+    pub fade: f32,
+    // within FONT_SIZES
+    pub font_size: u16,
+}
+
+// smaller is unreadable, bigger does not fit the glyph cache
+pub const FONT_SIZES: std::ops::RangeInclusive<u16> = 10..=200;
+
+impl Default for ScrollText {
+    fn default() -> Self {
+        ScrollText {
+            speed: 40.0,
+            top: 120.0,
+            bottom: 120.0,
+            fade: 100.0,
+            font_size: 60,
+        }
+    }
+}
+
+// How far the column of the current scene has scrolled, by time or by hand:
+// mouse wheel, dragging, arrow and page keys.
+pub struct TextScroller {
+    position: f32,
+    drag_from: Option<f32>,
+    // how far the mouse went since the button was pressed
+    dragged: f32,
+    scrolled_by_hand: f64,
+}
+
+impl TextScroller {
+    pub fn new() -> Self {
+        TextScroller {
+            position: 0.0,
+            drag_from: None,
+            dragged: 0.0,
+            scrolled_by_hand: f64::MIN,
+        }
+    }
+
+    pub fn restart(&mut self) {
+        *self = TextScroller::new();
+    }
+
+    // on a scrolling scene a click is a press let go in place, anything longer was a drag
+    pub fn is_click(&self) -> bool {
+        is_mouse_button_released(MouseButton::Left) && self.dragged < CLICK_SLOP
+    }
+
+    // `input` is off while something covers the scene
+    fn update(&mut self, config: &ScrollText, column_height: f32, max_position: f32, input: bool) {
+        let mut by_hand = 0.0;
+        if input {
+            let wheel = mouse_wheel().1;
+            if wheel != 0.0 {
+                by_hand -= wheel.signum() * WHEEL_STEP;
+            }
+            if is_key_pressed(KeyCode::Down) {
+                by_hand += KEY_STEP;
+            }
+            if is_key_pressed(KeyCode::Up) {
+                by_hand -= KEY_STEP;
+            }
+            if is_key_pressed(KeyCode::PageDown) {
+                by_hand += column_height;
+            }
+            if is_key_pressed(KeyCode::PageUp) {
+                by_hand -= column_height;
+            }
+
+            let mouse_y = mouse_position().1;
+            if is_mouse_button_down(MouseButton::Left) {
+                match self.drag_from {
+                    Some(from) => {
+                        by_hand += from - mouse_y;
+                        self.dragged += (from - mouse_y).abs();
+                    }
+                    None => self.dragged = 0.0,
+                }
+                self.drag_from = Some(mouse_y);
+            } else {
+                self.drag_from = None;
+            }
+        }
+
+        if by_hand != 0.0 {
+            self.position += by_hand;
+            self.scrolled_by_hand = clock::time();
+        } else if clock::time() - self.scrolled_by_hand > HAND_PAUSE {
+            self.position += config.speed * clock::frame_time();
+        }
+        self.position = self.position.clamp(0.0, max_position);
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    pub fn draw(
+        &mut self,
+        lines: &[RichLine],
+        fonts: &TextFonts,
+        icons: &InlineIcons,
+        window_size: (f32, f32),
+        config: &ScrollText,
+        style: &TextStyle,
+        colors: TextColors,
+        input: bool,
+    ) {
+        let column_top = config.top;
+        let column_bottom = window_size.1 - config.bottom;
+        let column_height = (column_bottom - column_top).max(0.0);
+
+        let heights = line_heights(lines, fonts, config.font_size);
+        let content_height: f32 = heights
+            .iter()
+            .map(|height| height + style.line_spacing)
+            .sum();
+        self.update(
+            config,
+            column_height,
+            (content_height - column_height).max(0.0),
+            input,
+        );
+
+        let mut top = column_top - self.position;
+        for (index, height) in heights.iter().enumerate() {
+            let baseline = top + height;
+            top = baseline + style.line_spacing;
+            if baseline < column_top || baseline - height > column_bottom {
+                continue;
+            }
+            // lines fade as they get close to either end of the column
+            let distance =
+                (baseline - height / 2.0 - column_top).min(column_bottom - baseline + height / 2.0);
+            let alpha = (distance / config.fade.max(1.0)).clamp(0.0, 1.0);
+            draw_text_block(
+                &lines[index..index + 1],
+                fonts,
+                icons,
+                config.font_size,
+                window_size,
+                &TextStyle {
+                    anchor: VerticalAnchor::Top,
+                    offset: baseline,
+                    ..style.clone()
+                },
+                TextColors {
+                    tint: Color {
+                        a: colors.tint.a * alpha,
+                        ..colors.tint
+                    },
+                    ..colors
+                },
+            );
+        }
+    }
+}
@@ -0,0 +1,133 @@
+#[cfg(target_arch = "wasm32")]
+use crate::platform;
+#[cfg(target_arch = "wasm32")]
+use crate::share::{link_payload, LINK_PREFIX};
+
+// see --render in cli.rs
+pub struct RenderSettings {
+    // a directory for numbered png frames, or a file ending in .gif
+    pub path: String,
+    pub fps: u32,
+    // seconds every scene is recorded for, its autoplay duration when unset
+    pub scene_seconds: Option<f32>,
+}
+
+pub struct Settings {
+    // a story file other than the one next to the game
+    pub story_path: Option<String>,
+    // a story packed in a link, played instead of any story file
+    pub story_link: Option<String>,
+    // index of the first scene shown
+    pub start_scene: Option<usize>,
+    pub fullscreen: bool,
+    pub window_size: Option<(i32, i32)>,
+    // language code like "ru" or "en", picked from the system locale when unset
+    pub language: Option<String>,
+    // makes particles and palettes come out the same on every run
+    pub seed: Option<u64>,
+    // nothing in the game plays sound yet, the flag is accepted so it can be passed already
+    #[allow(dead_code)]
+    pub mute: bool,
+    // scenes advance on their own instead of waiting for a click
+    pub autoplay: bool,
+    // the row of hearts counting the scenes
+    pub show_progress: bool,
+    // the overlay with frame times and scene details
+    pub debug: bool,
+    // screenshots are this many times the window size
+    pub screenshot_scale: f32,
+    // record the story instead of playing it, desktop only
+    pub render: Option<RenderSettings>,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Settings {
+            story_path: None,
+            story_link: None,
+            start_scene: None,
+            fullscreen: false,
+            window_size: None,
+            language: None,
+            seed: None,
+            mute: false,
+            autoplay: false,
+            show_progress: true,
+            debug: false,
+            screenshot_scale: 1.0,
+            render: None,
+        }
+    }
+}
+
+// "?autoplay", "?autoplay=1" and "?autoplay=true" all turn it on, "?progress=0" turns it off
+#[cfg(target_arch = "wasm32")]
+fn flag(name: &str, default: bool) -> bool {
+    platform::query_param(name).map_or(default, |value| {
+        !matches!(value.as_str(), "0" | "false" | "no")
+    })
+}
+
+// a value that does not parse is reported and ignored, a link should still open the card
+#[cfg(target_arch = "wasm32")]
+fn number<T: std::str::FromStr>(name: &str) -> Option<T> {
+    let value = platform::query_param(name)?;
+    let number = value.parse().ok();
+    if number.is_none() {
+        macroquad::logging::warn!("?{}={} is not a number, ignoring it", name, value);
+    }
+    number
+}
+
+impl Settings {
+    // the page url on the web, like index.html?scene=5&lang=en&autoplay
+    #[cfg(target_arch = "wasm32")]
+    pub fn from_environment() -> Settings {
+        Settings {
+            story_path: platform::query_param("story"),
+            story_link: platform::url_fragment()
+                .filter(|fragment| fragment.starts_with(LINK_PREFIX))
+                .map(|fragment| link_payload(&fragment).to_string()),
+            // counted from 1 like on the command line
+            start_scene: number::<usize>("scene").and_then(|scene| scene.checked_sub(1)),
+            language: platform::query_param("lang"),
+            seed: number("seed"),
+            mute: flag("mute", false),
+            autoplay: flag("autoplay", false),
+            show_progress: flag("progress", true),
+            debug: flag("debug", false),
+            screenshot_scale: number("screenshot_scale")
+                .filter(|scale: &f32| scale.is_finite() && *scale >= 1.0)
+                .unwrap_or(1.0),
+            ..Default::default()
+        }
+    }
+
+    // the command line on desktop, exits after printing help or a bad flag
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn from_environment() -> Settings {
+        use crate::cli::{parse_args, story_link, CliExit, HELP};
+
+        match parse_args(std::env::args().skip(1)) {
+            Ok(settings) => settings,
+            Err(CliExit::Help) => {
+                println!("{HELP}");
+                std::process::exit(0);
+            }
+            Err(CliExit::PrintLink(story_path)) => match story_link(&story_path) {
+                Ok(link) => {
+                    println!("#{link}");
+                    std::process::exit(0);
+                }
+                Err(message) => {
+                    eprintln!("error: {message}");
+                    std::process::exit(1);
+                }
+            },
+            Err(CliExit::Invalid(message)) => {
+                eprintln!("error: {message}\n\n{HELP}");
+                std::process::exit(2);
+            }
+        }
+    }
+}
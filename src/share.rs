@@ -0,0 +1,119 @@
+// A whole story packed into a link, so one hosted build can play many cards:
+// index.html#story=<deflated json in url-safe base64>
+
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
+use miniz_oxide::{deflate::compress_to_vec, inflate::decompress_to_vec_with_limit};
+
+pub const LINK_PREFIX: &str = "story=";
+// longer links get cut by messengers and some browsers
+pub const MAX_LINK_LENGTH: usize = 16 * 1024;
+// what a link may unpack to, so a crafted link cannot eat all the memory
+const MAX_STORY_SIZE: usize = 512 * 1024;
+const COMPRESSION_LEVEL: u8 = 10;
+
+// the part after "story=" in a full url, a "#story=..." fragment or the bare payload
+pub fn link_payload(link: &str) -> &str {
+    link.split_once(LINK_PREFIX)
+        .map_or(link, |(_, payload)| payload)
+}
+
+pub fn encode_story(json: &str) -> Result<String, String> {
+    // whitespace costs link length, so the json is written compactly first
+    let story: serde_json::Value = serde_json::from_str(json).map_err(|error| error.to_string())?;
+    let compressed = compress_to_vec(story.to_string().as_bytes(), COMPRESSION_LEVEL);
+    let link = format!("{LINK_PREFIX}{}", URL_SAFE_NO_PAD.encode(compressed));
+    if link.len() > MAX_LINK_LENGTH {
+        return Err(format!(
+            "the link would be {} characters long, at most {} fit",
+            link.len(),
+            MAX_LINK_LENGTH
+        ));
+    }
+    Ok(link)
+}
+
+pub fn decode_story(payload: &str) -> Result<Vec<u8>, String> {
+    if payload.len() > MAX_LINK_LENGTH {
+        return Err(format!(
+            "the link is {} characters long, at most {} are read",
+            payload.len(),
+            MAX_LINK_LENGTH
+        ));
+    }
+    let compressed = URL_SAFE_NO_PAD
+        .decode(payload.trim())
+        .map_err(|error| format!("the link is damaged: {}", error))?;
+    decompress_to_vec_with_limit(&compressed, MAX_STORY_SIZE).map_err(|_| {
+        format!(
+            "the link is damaged or unpacks to more than {} KiB",
+            MAX_STORY_SIZE / 1024
+        )
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::story::Story;
+
+    #[test]
+    fn round_trip_through_a_link() {
+        let json = r#"{ "a": [1, 2, 3], "text": "люблю" }"#;
+        let link = encode_story(json).unwrap();
+        assert!(link.starts_with(LINK_PREFIX));
+        let unpacked = decode_story(link_payload(&format!("https://example.com/#{link}"))).unwrap();
+        assert_eq!(
+            serde_json::from_slice::<serde_json::Value>(&unpacked).unwrap(),
+            serde_json::from_str::<serde_json::Value>(json).unwrap()
+        );
+    }
+
+    #[test]
+    fn payload_is_found_in_any_form() {
+        assert_eq!(link_payload("https://example.com/#story=abc"), "abc");
+        assert_eq!(link_payload("#story=abc"), "abc");
+        assert_eq!(link_payload("abc"), "abc");
+    }
+
+    #[test]
+    fn damaged_links_are_refused() {
+        assert!(encode_story("{ not json").is_err());
+        // not base64
+        assert!(decode_story("a*b").is_err());
+        // base64, but not deflated data
+        assert!(decode_story(&URL_SAFE_NO_PAD.encode(b"hello there")).is_err());
+        // a link cut short
+        let link = encode_story(r#"{"text": "a long enough story to cut in half"}"#).unwrap();
+        let payload = link_payload(&link);
+        assert!(decode_story(&payload[..payload.len() / 2]).is_err());
+    }
+
+    #[test]
+    fn oversized_links_are_refused() {
+        assert!(decode_story(&"A".repeat(MAX_LINK_LENGTH + 1)).is_err());
+        // tiny when packed, too big when unpacked
+        let bomb = compress_to_vec(&vec![b' '; MAX_STORY_SIZE + 1], COMPRESSION_LEVEL);
+        let payload = URL_SAFE_NO_PAD.encode(bomb);
+        assert!(payload.len() <= MAX_LINK_LENGTH);
+        assert!(decode_story(&payload).is_err());
+    }
+
+    #[test]
+    fn too_many_particles_are_refused() {
+        let json = std::fs::read_to_string("story.json").unwrap();
+        assert!(Story::from_json(&json).is_ok());
+        let mut story: serde_json::Value = serde_json::from_str(&json).unwrap();
+        story["scenes"][0]["palette"]["amount"] = 4_000_000_000u64.into();
+        assert!(Story::from_json(&story.to_string()).is_err());
+    }
+
+    #[test]
+    fn unusable_font_sizes_are_refused() {
+        let json = std::fs::read_to_string("story.json").unwrap();
+        let mut story: serde_json::Value = serde_json::from_str(&json).unwrap();
+        for (font_size, usable) in [(0, false), (10, true), (200, true), (201, false)] {
+            story["scenes"][0]["scroll"] = serde_json::json!({ "font_size": font_size });
+            assert_eq!(Story::from_json(&story.to_string()).is_ok(), usable);
+        }
+    }
+}
@@ -0,0 +1,83 @@
+use macroquad::prelude::*;
+
+use crate::clock;
+use crate::markup::{RichLine, Span};
+
+// how long a scene stays up when the story does not say: a pause plus reading time
+const BASE_DURATION: f32 = 4.0;
+const SECONDS_PER_LETTER: f32 = 0.07;
+const RING_SEGMENTS: usize = 64;
+const RING_THICKNESS: f32 = 6.0;
+const RING_MARGIN: f32 = 12.0;
+
+pub fn default_duration(texts: &[RichLine]) -> f32 {
+    let letters: usize = texts
+        .iter()
+        .flat_map(|line| &line.spans)
+        .map(|span| match span {
+            Span::Text { text, .. } => text.chars().filter(|c| !c.is_whitespace()).count(),
+            Span::Icon { .. } => 1,
+        })
+        .sum();
+    BASE_DURATION + letters as f32 * SECONDS_PER_LETTER
+}
+
+// Counts down the current scene and says when to move on.
+// Manual mode leaves progression to clicks only.
+pub struct Slideshow {
+    pub autoplay: bool,
+    // toggled with space, on top of the pause while the mouse is over the texture
+    pub paused: bool,
+    elapsed: f32,
+}
+
+impl Slideshow {
+    pub fn new(autoplay: bool) -> Self {
+        Slideshow {
+            autoplay,
+            paused: false,
+            elapsed: 0.0,
+        }
+    }
+
+    pub fn restart(&mut self) {
+        self.elapsed = 0.0;
+    }
+
+    // true once the scene has been shown for its whole duration
+    pub fn update(&mut self, duration: f32, hovered: bool) -> bool {
+        if !self.autoplay || self.paused || hovered {
+            return false;
+        }
+        self.elapsed += clock::frame_time();
+        self.elapsed >= duration
+    }
+
+    // the part of the ring still left runs clockwise from the top
+    pub fn draw_ring(&self, center: Vec2, radius: f32, duration: f32, hovered: bool) {
+        if !self.autoplay {
+            return;
+        }
+        let left = (1.0 - self.elapsed / duration).clamp(0.0, 1.0);
+        let alpha = if self.paused || hovered { 0.35 } else { 0.8 };
+        let radius = radius + RING_MARGIN;
+
+        let point = |fraction: f32| {
+            let angle = fraction * std::f32::consts::TAU - std::f32::consts::FRAC_PI_2;
+            center + vec2(angle.cos(), angle.sin()) * radius
+        };
+        let segments = (RING_SEGMENTS as f32 * left).ceil() as usize;
+        for segment in 0..segments {
+            let start = point(segment as f32 / RING_SEGMENTS as f32);
+            let end = point(((segment + 1) as f32 / RING_SEGMENTS as f32).min(left));
+            draw_line(
+                start.x,
+                start.y,
+                end.x,
+                end.y,
+                RING_THICKNESS,
+                Color::new(1.0, 1.0, 1.0, alpha),
+            );
+        }
+    }
+}
@@ -0,0 +1,473 @@
+use std::collections::BTreeMap;
+
+use macroquad::prelude::*;
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+
+use crate::animation::SceneAnimations;
+use crate::asset_paths::DEFAULT_MAX_TEXTURE_SIZE;
+use crate::ending::EndingConfig;
+use crate::palette::{Palette, MAX_PARTICLES};
+use crate::parallax::BackgroundLayer;
+use crate::scroll::{ScrollText, FONT_SIZES};
+use crate::text::TextStyle;
+use crate::theme::{theme_values, Theme, ThemeValues};
+
+#[derive(Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum WhatDraw {
+    Hearts,
+    Stars,
+}
+
+// A scene as the game uses it, with everything its theme fills in already resolved.
+#[derive(Clone)]
+pub struct SceneConfig {
+    pub theme: Option<String>,
+    pub what_draw: WhatDraw,
+    pub bg_color: Color,
+    pub texture: String,
+    // lines keyed by language code
+    pub texts: BTreeMap<String, Vec<String>>,
+    pub text_colors: (Color, Color),
+    pub palette: Palette,
+    pub layers: Vec<BackgroundLayer>,
+    pub text_style: TextStyle,
+    // seconds the scene stays up in autoplay, worked out from the text length when unset
+    pub duration: Option<f32>,
+    // keyframes played from the moment the scene is entered
+    pub animations: SceneAnimations,
+    // the texts scroll as one long column instead
+    pub scroll: Option<ScrollText>,
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(try_from = "StoryFile", into = "StoryFile")]
+pub struct Story {
+    pub default_language: String,
+    pub languages: Vec<String>,
+    // read by build.rs, which shrinks the images to fit it
+    pub max_texture_size: u32,
+    pub themes: BTreeMap<String, Theme>,
+    pub scenes: Vec<SceneConfig>,
+    // credits and stats shown after the last scene
+    pub ending: EndingConfig,
+}
+
+// The story as it is written in story.json: scenes with a theme may leave out
+// what the theme gives them, and are saved back without it.
+#[derive(Serialize, Deserialize)]
+struct StoryFile {
+    default_language: String,
+    languages: Vec<String>,
+    #[serde(default = "default_max_texture_size")]
+    max_texture_size: u32,
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    themes: BTreeMap<String, Theme>,
+    scenes: Vec<SceneFile>,
+    #[serde(default, skip_serializing_if = "EndingConfig::is_empty")]
+    ending: EndingConfig,
+}
+
+#[derive(Serialize, Deserialize)]
+struct SceneFile {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    theme: Option<String>,
+    what_draw: WhatDraw,
+    #[serde(
+        default,
+        with = "color_format::option",
+        skip_serializing_if = "Option::is_none"
+    )]
+    bg_color: Option<Color>,
+    texture: String,
+    texts: BTreeMap<String, Vec<String>>,
+    #[serde(
+        default,
+        with = "color_format::pair_option",
+        skip_serializing_if = "Option::is_none"
+    )]
+    text_colors: Option<(Color, Color)>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    palette: Option<Palette>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    layers: Vec<BackgroundLayer>,
+    // only the fields that differ from the default style, the font may come from the theme
+    #[serde(default, skip_serializing_if = "Map::is_empty")]
+    text_style: Map<String, Value>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    duration: Option<f32>,
+    #[serde(default, skip_serializing_if = "SceneAnimations::is_empty")]
+    animations: SceneAnimations,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    scroll: Option<ScrollText>,
+}
+
+fn missing(index: usize, field: &str) -> String {
+    format!("scene {index} has no {field} and no theme that gives it")
+}
+
+impl TryFrom<StoryFile> for Story {
+    type Error = String;
+
+    fn try_from(file: StoryFile) -> Result<Story, String> {
+        let scene_themes: Vec<Option<&str>> = file
+            .scenes
+            .iter()
+            .map(|scene| scene.theme.as_deref())
+            .collect();
+        let themed = theme_values(&file.themes, &scene_themes)?;
+
+        let mut scenes = vec![];
+        for (index, (scene, themed)) in file.scenes.into_iter().zip(themed).enumerate() {
+            let mut text_style = scene.text_style;
+            if let Some(font) = &themed.font {
+                if !text_style.contains_key("font") {
+                    let font = serde_json::to_value(font).map_err(|error| error.to_string())?;
+                    text_style.insert("font".to_string(), font);
+                }
+            }
+            scenes.push(SceneConfig {
+                theme: scene.theme,
+                what_draw: scene.what_draw,
+                bg_color: scene
+                    .bg_color
+                    .or(themed.bg_color)
+                    .ok_or_else(|| missing(index, "bg_color"))?,
+                texture: scene.texture,
+                texts: scene.texts,
+                text_colors: scene
+                    .text_colors
+                    .or(themed.text_colors)
+                    .ok_or_else(|| missing(index, "text_colors"))?,
+                palette: scene
+                    .palette
+                    .or(themed.palette)
+                    .ok_or_else(|| missing(index, "palette"))?,
+                layers: scene.layers,
+                text_style: serde_json::from_value(Value::Object(text_style))
+                    .map_err(|error| format!("scene {index}: text_style: {error}"))?,
+                duration: scene.duration,
+                animations: scene.animations,
+                scroll: scene.scroll,
+            });
+        }
+
+        Ok(Story {
+            default_language: file.default_language,
+            languages: file.languages,
+            max_texture_size: file.max_texture_size,
+            themes: file.themes,
+            scenes,
+            ending: file.ending,
+        })
+    }
+}
+
+// keeps only what the theme would not give anyway
+fn unless_themed<T: PartialEq>(value: T, themed: Option<T>) -> Option<T> {
+    Some(value).filter(|value| themed.as_ref() != Some(value))
+}
+
+impl From<Story> for StoryFile {
+    fn from(story: Story) -> StoryFile {
+        let scene_themes: Vec<Option<&str>> = story
+            .scenes
+            .iter()
+            .map(|scene| scene.theme.as_deref())
+            .collect();
+        // a theme removed while playing leaves its scenes with everything written out
+        let themed = theme_values(&story.themes, &scene_themes).unwrap_or_else(|_| {
+            story
+                .scenes
+                .iter()
+                .map(|_| ThemeValues::default())
+                .collect()
+        });
+        let default_style = to_map(&TextStyle::default());
+
+        let scenes = story
+            .scenes
+            .into_iter()
+            .zip(themed)
+            .map(|(scene, themed)| {
+                let mut text_style = to_map(&scene.text_style);
+                let themed_font = themed.font.as_ref().map(to_value);
+                text_style.retain(|key, value| match (key.as_str(), &themed_font) {
+                    ("font", Some(font)) => value != font,
+                    _ => default_style.get(key) != Some(value),
+                });
+                SceneFile {
+                    theme: scene.theme,
+                    what_draw: scene.what_draw,
+                    bg_color: unless_themed(scene.bg_color, themed.bg_color),
+                    texture: scene.texture,
+                    texts: scene.texts,
+                    text_colors: unless_themed(scene.text_colors, themed.text_colors),
+                    palette: unless_themed(scene.palette, themed.palette),
+                    layers: scene.layers,
+                    text_style,
+                    duration: scene.duration,
+                    animations: scene.animations,
+                    scroll: scene.scroll,
+                }
+            })
+            .collect();
+
+        StoryFile {
+            default_language: story.default_language,
+            languages: story.languages,
+            max_texture_size: story.max_texture_size,
+            themes: story.themes,
+            scenes,
+            ending: story.ending,
+        }
+    }
+}
+
+// through text, so f32 fields keep their short form instead of growing f64 digits
+fn to_value<T: Serialize>(value: &T) -> Value {
+    serde_json::to_string(value)
+        .ok()
+        .and_then(|json| serde_json::from_str(&json).ok())
+        .unwrap_or_default()
+}
+
+// for skip_serializing_if, so saving leaves out what loading fills in anyway
+pub fn is_default<T: Default + PartialEq>(value: &T) -> bool {
+    *value == T::default()
+}
+
+fn to_map<T: Serialize>(value: &T) -> Map<String, Value> {
+    match to_value(value) {
+        Value::Object(map) => map,
+        _ => Map::new(),
+    }
+}
+
+fn default_max_texture_size() -> u32 {
+    DEFAULT_MAX_TEXTURE_SIZE
+}
+
+impl SceneConfig {
+    pub fn texts_for<'a>(&'a self, language: &str, default_language: &str) -> &'a [String] {
+        self.texts
+            .get(language)
+            .or_else(|| self.texts.get(default_language))
+            .map(|texts| texts.as_slice())
+            .unwrap_or_default()
+    }
+}
+
+impl Story {
+    pub fn from_json(json: &str) -> Result<Story, String> {
+        let story: Story = serde_json::from_str(json).map_err(|error| error.to_string())?;
+        if story.scenes.is_empty() {
+            return Err("the story has no scenes".to_string());
+        }
+        if let Some(index) = story
+            .scenes
+            .iter()
+            .position(|scene| scene.palette.amount > MAX_PARTICLES)
+        {
+            return Err(format!(
+                "scene {index} asks for more than {MAX_PARTICLES} particles"
+            ));
+        }
+        if let Some(index) = story.scenes.iter().position(|scene| {
+            scene
+                .scroll
+                .as_ref()
+                .is_some_and(|scroll| !FONT_SIZES.contains(&scroll.font_size))
+        }) {
+            return Err(format!(
+                "scene {index} has a font size outside {} to {}",
+                FONT_SIZES.start(),
+                FONT_SIZES.end()
+            ));
+        }
+        Ok(story)
+    }
+
+    // the first candidate the story is translated to, the default language otherwise
+    pub fn pick_language(&self, candidates: &[Option<String>]) -> String {
+        candidates
+            .iter()
+            .flatten()
+            .map(|candidate| normalize_language(candidate))
+            .find(|language| self.languages.contains(language))
+            .unwrap_or_else(|| self.default_language.clone())
+    }
+
+    pub fn next_language(&self, language: &str) -> String {
+        let index = self
+            .languages
+            .iter()
+            .position(|known| known == language)
+            .map_or(0, |index| (index + 1) % self.languages.len());
+        self.languages
+            .get(index)
+            .cloned()
+            .unwrap_or_else(|| self.default_language.clone())
+    }
+
+    pub fn report_missing_translations(&self) {
+        let mut missing_total = 0;
+        for (scene_index, scene) in self.scenes.iter().enumerate() {
+            for language in &self.languages {
+                if !scene.texts.contains_key(language) {
+                    warn!("scene {}: no \"{}\" translation", scene_index, language);
+                    missing_total += 1;
+                }
+            }
+        }
+        if missing_total > 0 {
+            warn!("{} scene translations are missing", missing_total);
+        }
+    }
+}
+
+// "ru-RU", "ru_RU.UTF-8" and "RU" all become "ru"
+pub fn normalize_language(language: &str) -> String {
+    language
+        .split(['-', '_', '.'])
+        .next()
+        .unwrap_or_default()
+        .to_lowercase()
+}
+
+// Colors are written as [r, g, b, a] and can also be read from [r, g, b],
+// "#rrggbb", "#rrggbbaa" or a name like "white"
+pub mod color_format {
+    use macroquad::prelude::Color;
+    use serde::{de::Error, Deserialize, Deserializer, Serialize, Serializer};
+
+    use crate::markup::{color_name, parse_color};
+
+    #[derive(Serialize, Deserialize)]
+    #[serde(untagged)]
+    enum ColorValue {
+        Text(String),
+        Channels(Vec<f32>),
+    }
+
+    // a name when the color has one, no alpha channel when it is opaque
+    fn to_value(color: &Color) -> ColorValue {
+        match color_name(*color) {
+            Some(name) => ColorValue::Text(name.to_string()),
+            None if color.a == 1.0 => ColorValue::Channels(vec![color.r, color.g, color.b]),
+            None => ColorValue::Channels(vec![color.r, color.g, color.b, color.a]),
+        }
+    }
+
+    fn from_value(value: ColorValue) -> Result<Color, String> {
+        match value {
+            ColorValue::Text(text) => parse_color(&text).ok_or(format!("unknown color {text:?}")),
+            ColorValue::Channels(channels) => match channels[..] {
+                [r, g, b] => Ok(Color::new(r, g, b, 1.0)),
+                [r, g, b, a] => Ok(Color::new(r, g, b, a)),
+                _ => Err(format!("a color needs 3 or 4 channels, got {:?}", channels)),
+            },
+        }
+    }
+
+    pub fn serialize<S: Serializer>(color: &Color, serializer: S) -> Result<S::Ok, S::Error> {
+        to_value(color).serialize(serializer)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Color, D::Error> {
+        from_value(ColorValue::deserialize(deserializer)?).map_err(D::Error::custom)
+    }
+
+    pub mod option {
+        use super::*;
+
+        pub fn serialize<S: Serializer>(
+            color: &Option<Color>,
+            serializer: S,
+        ) -> Result<S::Ok, S::Error> {
+            color.as_ref().map(to_value).serialize(serializer)
+        }
+
+        pub fn deserialize<'de, D: Deserializer<'de>>(
+            deserializer: D,
+        ) -> Result<Option<Color>, D::Error> {
+            Option::<ColorValue>::deserialize(deserializer)?
+                .map(from_value)
+                .transpose()
+                .map_err(D::Error::custom)
+        }
+    }
+
+    pub mod list {
+        use super::*;
+
+        pub fn serialize<S: Serializer>(
+            colors: &[Color],
+            serializer: S,
+        ) -> Result<S::Ok, S::Error> {
+            colors
+                .iter()
+                .map(to_value)
+                .collect::<Vec<_>>()
+                .serialize(serializer)
+        }
+
+        pub fn deserialize<'de, D: Deserializer<'de>>(
+            deserializer: D,
+        ) -> Result<Vec<Color>, D::Error> {
+            Vec::<ColorValue>::deserialize(deserializer)?
+                .into_iter()
+                .map(from_value)
+                .collect::<Result<_, _>>()
+                .map_err(D::Error::custom)
+        }
+    }
+
+    pub mod pair_option {
+        use super::*;
+
+        pub fn serialize<S: Serializer>(
+            colors: &Option<(Color, Color)>,
+            serializer: S,
+        ) -> Result<S::Ok, S::Error> {
+            colors
+                .as_ref()
+                .map(|(first, second)| (to_value(first), to_value(second)))
+                .serialize(serializer)
+        }
+
+        pub fn deserialize<'de, D: Deserializer<'de>>(
+            deserializer: D,
+        ) -> Result<Option<(Color, Color)>, D::Error> {
+            Option::<(ColorValue, ColorValue)>::deserialize(deserializer)?
+                .map(|(first, second)| Ok::<_, String>((from_value(first)?, from_value(second)?)))
+                .transpose()
+                .map_err(D::Error::custom)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn saving_the_bundled_story_writes_it_back_unchanged() {
+        let json = std::fs::read_to_string("story.json").unwrap();
+        let story = Story::from_json(&json).unwrap();
+        assert!(serde_json::to_string_pretty(&story).unwrap() == json);
+    }
+
+    #[test]
+    fn colors_are_written_by_name_or_channels() {
+        let color = |color: Color| {
+            let mut json = vec![];
+            color_format::serialize(&color, &mut serde_json::Serializer::new(&mut json)).unwrap();
+            String::from_utf8(json).unwrap()
+        };
+        assert_eq!(color(WHITE), r#""white""#);
+        assert_eq!(color(Color::new(1.0, 0.5, 0.25, 1.0)), "[1.0,0.5,0.25]");
+        assert_eq!(color(Color::new(0.0, 0.0, 0.3, 0.5)), "[0.0,0.0,0.3,0.5]");
+    }
+}
@@ -0,0 +1,615 @@
+use macroquad::{prelude::*, rand};
+use serde::{Deserialize, Serialize};
+
+use crate::animation::tint;
+use crate::asset_paths::FONT_PATHS;
+use crate::clock;
+use crate::fonts::{font_runs, pick_font, LoadedFont};
+use crate::handwriting::{draw_pen, Handwriting, HandwritingClip};
+use crate::markup::{Effect, Icon, RichLine, Span, SpanStyle};
+use crate::story::color_format;
+
+const LINES_SPACING: f32 = 15.0;
+const BLUR_SAMPLES: usize = 8;
+
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum VerticalAnchor {
+    Top,
+    Center,
+    Bottom,
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Alignment {
+    Left,
+    Center,
+    Right,
+}
+
+#[derive(Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FontChoice {
+    Fixed(usize),
+    // switches to the next font every `interval` seconds
+    Cycle { interval: f32 },
+    // the original behaviour: a 3% chance to switch every frame
+    Random,
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+pub struct Outline {
+    pub thickness: f32,
+    // defaults to the second scene text color
+    #[serde(
+        default,
+        with = "color_format::option",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub color: Option<Color>,
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+pub struct Shadow {
+    pub offset: (f32, f32),
+    // defaults to the second scene text color
+    #[serde(
+        default,
+        with = "color_format::option",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub color: Option<Color>,
+    pub blur: f32,
+}
+
+// The "text_style" of a scene, only the fields that differ from the default, e.g.
+//   {"outline": {"thickness": 3}, "shadow": null}
+//   {"shadow": {"offset": [8, 8], "color": [0, 0, 0.3, 0.5], "blur": 4}}
+//   {"alignment": "right", "anchor": "bottom", "offset": 80}
+//   {"font": {"cycle": {"interval": 1.5}}, "font_stack": [1, 0]}
+//   {"anchor": "center", "offset": 0, "font": {"fixed": 2}, "line_spacing": 5}
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct TextStyle {
+    pub outline: Option<Outline>,
+    pub shadow: Option<Shadow>,
+    // the texts are written line by line when the scene starts instead of shown at once
+    pub handwriting: Option<Handwriting>,
+    pub anchor: VerticalAnchor,
+    // distance from the anchor, for `Top` it is where the first line baseline sits
+    pub offset: f32,
+    pub alignment: Alignment,
+    pub side_margin: f32,
+    pub font: FontChoice,
+    // indices of the fonts to fall back to for missing glyphs, in order
+    pub font_stack: Vec<usize>,
+    pub line_spacing: f32,
+}
+
+impl Default for TextStyle {
+    fn default() -> Self {
+        TextStyle {
+            outline: None,
+            shadow: Some(Shadow {
+                offset: (5.0, 5.0),
+                color: None,
+                blur: 0.0,
+            }),
+            handwriting: None,
+            anchor: VerticalAnchor::Top,
+            offset: 300.0,
+            alignment: Alignment::Center,
+            side_margin: 40.0,
+            font: FontChoice::Random,
+            font_stack: (0..FONT_PATHS.len()).collect(),
+            line_spacing: LINES_SPACING,
+        }
+    }
+}
+
+pub struct TextFonts<'a> {
+    pub regular: &'a LoadedFont,
+    pub bold: &'a LoadedFont,
+    // tried in order when the regular or bold font lacks a glyph
+    pub fallbacks: Vec<&'a LoadedFont>,
+}
+
+pub struct InlineIcons<'a> {
+    pub heart: &'a Texture2D,
+    pub star: &'a Texture2D,
+}
+
+impl TextStyle {
+    pub fn fallback_fonts<'a>(&self, fonts: &'a [LoadedFont]) -> Vec<&'a LoadedFont> {
+        self.font_stack
+            .iter()
+            .filter_map(|index| fonts.get(*index))
+            .collect()
+    }
+}
+
+pub struct FontCycler {
+    pub index: usize,
+    last_switch: f64,
+}
+
+impl FontCycler {
+    pub fn new() -> Self {
+        FontCycler {
+            index: 0,
+            last_switch: clock::time(),
+        }
+    }
+
+    fn next(&mut self, fonts_amount: usize) {
+        self.index = (self.index + 1) % fonts_amount;
+        self.last_switch = clock::time();
+    }
+
+    pub fn update(&mut self, choice: &FontChoice, fonts_amount: usize) {
+        match choice {
+            FontChoice::Fixed(index) => self.index = (*index).min(fonts_amount - 1),
+            FontChoice::Cycle { interval } => {
+                if clock::time() - self.last_switch > *interval as f64 {
+                    self.next(fonts_amount);
+                }
+            }
+            FontChoice::Random => {
+                if rand::gen_range(0, 100) > 97 {
+                    self.next(fonts_amount);
+                }
+            }
+        }
+    }
+}
+
+#[derive(Clone, Copy)]
+pub struct TextColors {
+    pub primary: Color,
+    pub secondary: Color,
+    // multiplies every color, including the ones set by markup or the text style
+    pub tint: Color,
+}
+
+impl TextStyle {
+    fn tinted(&self, color: Color) -> TextStyle {
+        let mut style = self.clone();
+        if let Some(outline) = &mut style.outline {
+            outline.color = outline
+                .color
+                .map(|outline_color| tint(outline_color, color));
+        }
+        if let Some(shadow) = &mut style.shadow {
+            shadow.color = shadow.color.map(|shadow_color| tint(shadow_color, color));
+        }
+        style
+    }
+}
+
+fn draw_text_simple(text: &str, x: f32, y: f32, size: f32, color: Color, font: &Font) {
+    draw_text_ex(
+        text,
+        x,
+        y,
+        TextParams {
+            font: Some(font),
+            font_size: size as u16,
+            color,
+            ..Default::default()
+        },
+    );
+}
+
+fn draw_text_styled(
+    text: &str,
+    (x, y): (f32, f32),
+    size: f32,
+    style: &TextStyle,
+    (primary_color, secondary_color): (Color, Color),
+    font: &Font,
+) {
+    if let Some(shadow) = &style.shadow {
+        let color = shadow.color.unwrap_or(secondary_color);
+        let (shadow_x, shadow_y) = (x + shadow.offset.0, y + shadow.offset.1);
+        if shadow.blur > 0.0 {
+            let faded = Color {
+                a: color.a * 2.0 / BLUR_SAMPLES as f32,
+                ..color
+            };
+            for sample in 0..BLUR_SAMPLES {
+                let angle = sample as f32 / BLUR_SAMPLES as f32 * std::f32::consts::TAU;
+                draw_text_simple(
+                    text,
+                    shadow_x + angle.cos() * shadow.blur,
+                    shadow_y + angle.sin() * shadow.blur,
+                    size,
+                    faded,
+                    font,
+                );
+            }
+        } else {
+            draw_text_simple(text, shadow_x, shadow_y, size, color, font);
+        }
+    }
+
+    if let Some(outline) = &style.outline {
+        let color = outline.color.unwrap_or(secondary_color);
+        for (dx, dy) in [
+            (-1.0, -1.0),
+            (0.0, -1.0),
+            (1.0, -1.0),
+            (-1.0, 0.0),
+            (1.0, 0.0),
+            (-1.0, 1.0),
+            (0.0, 1.0),
+            (1.0, 1.0),
+        ] {
+            draw_text_simple(
+                text,
+                x + dx * outline.thickness,
+                y + dy * outline.thickness,
+                size,
+                color,
+                font,
+            );
+        }
+    }
+
+    draw_text_simple(text, x, y, size, primary_color, font);
+}
+
+// pieces drawn with a single call each: font runs for static text, letters for animated text
+fn span_pieces<'t, 'a>(
+    text: &'t str,
+    style: &SpanStyle,
+    fonts: &TextFonts<'a>,
+) -> Vec<(&'t str, &'a LoadedFont)> {
+    let primary = if style.bold {
+        fonts.bold
+    } else {
+        fonts.regular
+    };
+    if style.effect == Effect::None {
+        return font_runs(text, primary, &fonts.fallbacks);
+    }
+    text.char_indices()
+        .map(|(position, letter)| {
+            (
+                &text[position..position + letter.len_utf8()],
+                pick_font(letter, primary, &fonts.fallbacks),
+            )
+        })
+        .collect()
+}
+
+fn letter_offset(effect: Effect, letter_index: usize, size: f32) -> (f32, f32) {
+    match effect {
+        Effect::None => (0.0, 0.0),
+        Effect::Shake => (
+            rand::gen_range(-0.04, 0.04) * size,
+            rand::gen_range(-0.04, 0.04) * size,
+        ),
+        Effect::Wave => (
+            0.0,
+            (clock::time() as f32 * 6.0 + letter_index as f32 * 0.6).sin() * size * 0.08,
+        ),
+    }
+}
+
+fn measure_span(span: &Span, fonts: &TextFonts, font_size: u16) -> (f32, f32) {
+    match span {
+        Span::Text { text, style } => {
+            let size = (font_size as f32 * style.scale) as u16;
+            span_pieces(text, style, fonts)
+                .into_iter()
+                .map(|(piece, font)| measure_text(piece, Some(&font.font), size, 1.0))
+                .fold((0.0, 0.0), |(width, height), piece_size| {
+                    (
+                        width + piece_size.width,
+                        f32::max(height, piece_size.height),
+                    )
+                })
+        }
+        Span::Icon { style, .. } => {
+            let icon_size = font_size as f32 * style.scale * 0.8;
+            (icon_size, icon_size)
+        }
+    }
+}
+
+// how tall every line is drawn, the tallest span decides
+pub fn line_heights(lines: &[RichLine], fonts: &TextFonts, font_size: u16) -> Vec<f32> {
+    lines
+        .iter()
+        .map(|line| {
+            line.spans
+                .iter()
+                .map(|span| measure_span(span, fonts, font_size).1)
+                .fold(0.0, f32::max)
+        })
+        .collect()
+}
+
+// how wide every line is drawn
+pub fn line_widths(lines: &[RichLine], fonts: &TextFonts, font_size: u16) -> Vec<f32> {
+    lines
+        .iter()
+        .map(|line| {
+            line.spans
+                .iter()
+                .map(|span| measure_span(span, fonts, font_size).0)
+                .sum()
+        })
+        .collect()
+}
+
+fn draw_icon(
+    texture: &Texture2D,
+    (x, y): (f32, f32),
+    icon_size: f32,
+    style: &TextStyle,
+    color: Color,
+    secondary_color: Color,
+) {
+    let params = DrawTextureParams {
+        dest_size: Some(vec2(icon_size, icon_size)),
+        ..Default::default()
+    };
+    let top = y - icon_size;
+    if let Some(shadow) = &style.shadow {
+        draw_texture_ex(
+            texture,
+            x + shadow.offset.0,
+            top + shadow.offset.1,
+            shadow.color.unwrap_or(secondary_color),
+            params.clone(),
+        );
+    }
+    draw_texture_ex(texture, x, top, color, params);
+}
+
+fn draw_span(
+    span: &Span,
+    position: (f32, f32),
+    fonts: &TextFonts,
+    icons: &InlineIcons,
+    font_size: u16,
+    style: &TextStyle,
+    colors: TextColors,
+) {
+    let secondary = tint(colors.secondary, colors.tint);
+    match span {
+        Span::Text {
+            text,
+            style: span_style,
+        } => {
+            let size = font_size as f32 * span_style.scale;
+            let primary = tint(span_style.color.unwrap_or(colors.primary), colors.tint);
+            let mut x = position.0;
+            for (index, (piece, font)) in
+                span_pieces(text, span_style, fonts).into_iter().enumerate()
+            {
+                let (dx, dy) = letter_offset(span_style.effect, index, size);
+                draw_text_styled(
+                    piece,
+                    (x + dx, position.1 + dy),
+                    size,
+                    style,
+                    (primary, secondary),
+                    &font.font,
+                );
+                x += measure_text(piece, Some(&font.font), size as u16, 1.0).width;
+            }
+        }
+        Span::Icon {
+            icon,
+            style: span_style,
+        } => {
+            let texture = match icon {
+                Icon::Heart => icons.heart,
+                Icon::Star => icons.star,
+            };
+            let icon_size = font_size as f32 * span_style.scale * 0.8;
+            let (dx, dy) = letter_offset(span_style.effect, 0, icon_size);
+            draw_icon(
+                texture,
+                (position.0 + dx, position.1 + dy),
+                icon_size,
+                style,
+                tint(span_style.color.unwrap_or(WHITE), colors.tint),
+                secondary,
+            );
+        }
+    }
+}
+
+fn line_x(line_width: f32, style: &TextStyle, window_size: (f32, f32)) -> f32 {
+    match style.alignment {
+        Alignment::Left => style.side_margin,
+        Alignment::Center => (window_size.0 - line_width) / 2.0,
+        Alignment::Right => window_size.0 - style.side_margin - line_width,
+    }
+}
+
+fn first_baseline(heights: &[f32], style: &TextStyle, window_size: (f32, f32)) -> f32 {
+    let block_height: f32 =
+        heights.iter().sum::<f32>() + style.line_spacing * heights.len().saturating_sub(1) as f32;
+    let first_height = heights.first().copied().unwrap_or(0.0);
+    match style.anchor {
+        VerticalAnchor::Top => style.offset,
+        VerticalAnchor::Center => {
+            (window_size.1 - block_height) / 2.0 + first_height + style.offset
+        }
+        VerticalAnchor::Bottom => window_size.1 - style.offset - block_height + first_height,
+    }
+}
+
+pub fn draw_text_block(
+    lines: &[RichLine],
+    fonts: &TextFonts,
+    icons: &InlineIcons,
+    font_size: u16,
+    window_size: (f32, f32),
+    style: &TextStyle,
+    colors: TextColors,
+) -> f32 {
+    draw_lines(
+        lines,
+        fonts,
+        icons,
+        font_size,
+        window_size,
+        style,
+        colors,
+        None,
+    )
+}
+
+// How far a text with handwriting got.
+pub struct Writing<'a> {
+    pub clip: &'a HandwritingClip,
+    // seconds since the scene started
+    pub elapsed: f32,
+}
+
+// like draw_text_block, but only what the pen of `style.handwriting` wrote so far
+#[allow(clippy::too_many_arguments)]
+pub fn draw_text_written(
+    lines: &[RichLine],
+    fonts: &TextFonts,
+    icons: &InlineIcons,
+    font_size: u16,
+    window_size: (f32, f32),
+    style: &TextStyle,
+    colors: TextColors,
+    writing: Writing,
+) -> f32 {
+    draw_lines(
+        lines,
+        fonts,
+        icons,
+        font_size,
+        window_size,
+        style,
+        colors,
+        Some(writing),
+    )
+}
+
+#[allow(clippy::too_many_arguments)]
+fn draw_lines(
+    lines: &[RichLine],
+    fonts: &TextFonts,
+    icons: &InlineIcons,
+    font_size: u16,
+    window_size: (f32, f32),
+    style: &TextStyle,
+    colors: TextColors,
+    writing: Option<Writing>,
+) -> f32 {
+    let written = writing.as_ref().and_then(|writing| {
+        style
+            .handwriting
+            .as_ref()
+            .map(|handwriting| (handwriting, handwriting.written(writing.elapsed)))
+    });
+    let style = &style.tinted(colors.tint);
+    let sizes: Vec<Vec<(f32, f32)>> = lines
+        .iter()
+        .map(|line| {
+            line.spans
+                .iter()
+                .map(|span| measure_span(span, fonts, font_size))
+                .collect()
+        })
+        .collect();
+    let heights: Vec<f32> = sizes
+        .iter()
+        .map(|line| line.iter().map(|size| size.1).fold(0.0, f32::max))
+        .collect();
+
+    let first = first_baseline(&heights, style, window_size);
+    let mut y = first;
+    // how much of the lines above the pen went through
+    let mut written_before = 0.0;
+    for ((line, line_sizes), height) in lines.iter().zip(&sizes).zip(&heights) {
+        let line_width: f32 = line_sizes.iter().map(|size| size.0).sum();
+        let start = line_x(line_width, style, window_size);
+        // how far into this line the pen is, when it is writing it
+        let mut cut = None;
+        if let Some((_, written)) = written {
+            let into_line = written - written_before;
+            written_before += line_width;
+            if into_line <= 0.0 {
+                y += height + style.line_spacing;
+                continue;
+            }
+            if into_line < line_width {
+                cut = Some(start + into_line);
+            }
+        }
+
+        if let (Some(cut_x), Some(writing)) = (cut, &writing) {
+            writing.clip.begin(cut_x);
+        }
+        let mut x = start;
+        for (span, size) in line.spans.iter().zip(line_sizes) {
+            draw_span(span, (x, y), fonts, icons, font_size, style, colors);
+            x += size.0;
+        }
+        if let (Some(cut_x), Some(writing), Some((handwriting, _))) = (cut, &writing, written) {
+            writing.clip.end();
+            if handwriting.pen {
+                draw_pen(
+                    vec2(cut_x, y),
+                    *height,
+                    writing.elapsed,
+                    tint(colors.primary, colors.tint),
+                    tint(colors.secondary, colors.tint),
+                );
+            }
+        }
+        y += height + style.line_spacing;
+    }
+    // how tall the block came out
+    y - first
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn style(json: &str) -> TextStyle {
+        serde_json::from_str(json).unwrap()
+    }
+
+    #[test]
+    fn fields_left_out_keep_the_default() {
+        let outlined = style(r#"{"outline": {"thickness": 3}, "shadow": null}"#);
+        assert_eq!(outlined.outline.map(|outline| outline.thickness), Some(3.0));
+        assert!(outlined.shadow.is_none());
+        assert!(matches!(outlined.alignment, Alignment::Center));
+        assert_eq!(outlined.line_spacing, LINES_SPACING);
+
+        let placed = style(r#"{"alignment": "right", "anchor": "bottom", "offset": 80}"#);
+        assert!(matches!(placed.alignment, Alignment::Right));
+        assert!(matches!(placed.anchor, VerticalAnchor::Bottom));
+        assert_eq!(placed.offset, 80.0);
+        assert!(placed.shadow.is_some());
+    }
+
+    #[test]
+    fn shadows_and_fonts() {
+        let shadowed = style(
+            r#"{"shadow": {"offset": [8, 8], "color": [0, 0, 0.3, 0.5], "blur": 4},
+                "font": {"cycle": {"interval": 1.5}}}"#,
+        );
+        let shadow = shadowed.shadow.unwrap();
+        assert_eq!((shadow.offset, shadow.blur), ((8.0, 8.0), 4.0));
+        assert_eq!(shadow.color, Some(Color::new(0.0, 0.0, 0.3, 0.5)));
+        assert!(shadowed.font == FontChoice::Cycle { interval: 1.5 });
+        assert!(style(r#"{"font": {"fixed": 2}}"#).font == FontChoice::Fixed(2));
+        assert!(style(r#"{"font": "random"}"#).font == FontChoice::Random);
+    }
+}
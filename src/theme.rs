@@ -0,0 +1,189 @@
+use std::collections::BTreeMap;
+
+use macroquad::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::palette::Palette;
+use crate::story::{color_format, is_default};
+use crate::text::FontChoice;
+
+// A named look shared by scenes. A scene that names a theme takes every field
+// it leaves out from it:
+//   "themes": {"love": {"bg_color": [1.0, 0.75, 0.7], "progression": {"darken": 0.05}}}
+//   {"theme": "love", "texture": .., "texts": ..}
+#[derive(Clone, Serialize, Deserialize)]
+pub struct Theme {
+    #[serde(
+        default,
+        with = "color_format::option",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub bg_color: Option<Color>,
+    #[serde(
+        default,
+        with = "color_format::pair_option",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub text_colors: Option<(Color, Color)>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub palette: Option<Palette>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub font: Option<FontChoice>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub progression: Option<Progression>,
+}
+
+// How the background changes from one scene of the theme to the next,
+// counted from the first scene that uses it.
+#[derive(Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Progression {
+    // share of the brightness taken away every scene, negative values lighten
+    #[serde(skip_serializing_if = "is_default")]
+    pub darken: f32,
+    // added to the red, green and blue channels every scene
+    #[serde(skip_serializing_if = "is_default")]
+    pub shift: (f32, f32, f32),
+}
+
+impl Progression {
+    fn apply(&self, color: Color, step: usize) -> Color {
+        let factor = (1.0 - self.darken).powi(step as i32);
+        let step = step as f32;
+        Color::new(
+            (color.r * factor + self.shift.0 * step).clamp(0.0, 1.0),
+            (color.g * factor + self.shift.1 * step).clamp(0.0, 1.0),
+            (color.b * factor + self.shift.2 * step).clamp(0.0, 1.0),
+            color.a,
+        )
+    }
+}
+
+// what a theme gives one of its scenes
+#[derive(Default)]
+pub struct ThemeValues {
+    pub bg_color: Option<Color>,
+    pub text_colors: Option<(Color, Color)>,
+    pub palette: Option<Palette>,
+    pub font: Option<FontChoice>,
+}
+
+impl Theme {
+    fn values(&self, step: usize) -> ThemeValues {
+        ThemeValues {
+            bg_color: self.bg_color.map(|color| match &self.progression {
+                Some(progression) => progression.apply(color, step),
+                None => color,
+            }),
+            text_colors: self.text_colors,
+            palette: self.palette.clone(),
+            font: self.font.clone(),
+        }
+    }
+}
+
+// the values every scene gets from its theme, given the theme name of each scene in order
+pub fn theme_values(
+    themes: &BTreeMap<String, Theme>,
+    scene_themes: &[Option<&str>],
+) -> Result<Vec<ThemeValues>, String> {
+    let mut steps: BTreeMap<&str, usize> = BTreeMap::new();
+    scene_themes
+        .iter()
+        .enumerate()
+        .map(|(index, name)| {
+            let Some(name) = name else {
+                return Ok(ThemeValues::default());
+            };
+            let theme = themes
+                .get(*name)
+                .ok_or_else(|| format!("scene {index}: there is no theme named {name:?}"))?;
+            let step = steps.entry(name).or_insert(0);
+            *step += 1;
+            Ok(theme.values(*step - 1))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn themes(json: &str) -> BTreeMap<String, Theme> {
+        serde_json::from_str(json).unwrap()
+    }
+
+    fn close(a: Color, b: Color) -> bool {
+        [a.r - b.r, a.g - b.g, a.b - b.b, a.a - b.a]
+            .iter()
+            .all(|difference| difference.abs() < 1e-4)
+    }
+
+    fn backgrounds(values: &[ThemeValues]) -> Vec<Option<Color>> {
+        values.iter().map(|values| values.bg_color).collect()
+    }
+
+    #[test]
+    fn darken_and_shift() {
+        let progression = Progression {
+            darken: 0.5,
+            shift: (0.0, 0.1, -0.1),
+        };
+        let start = Color::new(0.8, 0.4, 0.2, 0.5);
+        assert!(close(progression.apply(start, 0), start));
+        assert!(close(
+            progression.apply(start, 2),
+            Color::new(0.2, 0.3, 0.0, 0.5)
+        ));
+        let lighten = Progression {
+            darken: -1.0,
+            ..Progression::default()
+        };
+        assert!(close(
+            lighten.apply(start, 1),
+            Color::new(1.0, 0.8, 0.4, 0.5)
+        ));
+    }
+
+    #[test]
+    fn every_theme_counts_its_own_scenes() {
+        let themes = themes(
+            r#"{"dusk": {"bg_color": [1, 1, 1], "progression": {"darken": 0.5}},
+                "plain": {"bg_color": [0, 0, 1], "font": "random"}}"#,
+        );
+        let values = theme_values(
+            &themes,
+            &[
+                Some("dusk"),
+                None,
+                Some("plain"),
+                Some("dusk"),
+                Some("dusk"),
+            ],
+        )
+        .unwrap();
+        let grey = |value| Some(Color::new(value, value, value, 1.0));
+        assert_eq!(
+            backgrounds(&values),
+            vec![
+                grey(1.0),
+                None,
+                Some(Color::new(0.0, 0.0, 1.0, 1.0)),
+                grey(0.5),
+                grey(0.25)
+            ]
+        );
+        assert!(values[2].font == Some(FontChoice::Random));
+        assert!(values[1].font.is_none());
+    }
+
+    #[test]
+    fn unknown_themes_are_errors() {
+        let themes = themes(r#"{"love": {}}"#);
+        let error = theme_values(&themes, &[Some("love"), Some("lvoe")]).err();
+        assert_eq!(
+            error.as_deref(),
+            Some(r#"scene 1: there is no theme named "lvoe""#)
+        );
+    }
+}
@@ -0,0 +1,57 @@
+// Browser side of src/platform.rs, load it after gl.js.
+"use strict";
+
+function anniversary_write_string(text, buffer, max_length) {
+    const bytes = new TextEncoder().encode(text);
+    const length = Math.min(bytes.length, max_length);
+    new Uint8Array(wasm_memory.buffer, buffer, length).set(bytes.subarray(0, length));
+    return length;
+}
+
+miniquad_add_plugin({
+    register_plugin: function (importObject) {
+        importObject.env.anniversary_browser_language = function (buffer, max_length) {
+            const language = navigator.language || "";
+            return anniversary_write_string(language, buffer, max_length);
+        };
+        importObject.env.anniversary_query_param = function (name, name_length, buffer, max_length) {
+            const key = UTF8ToString(name, name_length);
+            const value = new URLSearchParams(window.location.search).get(key);
+            if (value === null) {
+                return -1;
+            }
+            return anniversary_write_string(value, buffer, max_length);
+        };
+        // the fragment is never sent to the server, which keeps story links private
+        importObject.env.anniversary_url_fragment_length = function () {
+            const fragment = window.location.hash;
+            if (fragment.length <= 1) {
+                return -1;
+            }
+            return new TextEncoder().encode(fragment.slice(1)).length;
+        };
+        importObject.env.anniversary_url_fragment = function (buffer, max_length) {
+            if (window.location.hash.length <= 1) {
+                return -1;
+            }
+            return anniversary_write_string(window.location.hash.slice(1), buffer, max_length);
+        };
+        importObject.env.anniversary_download = function (name, name_length, mime_type, mime_type_length, data, data_length) {
+            // copied out, the wasm memory can move once the game allocates again
+            const bytes = new Uint8Array(wasm_memory.buffer, data, data_length).slice();
+            const type = UTF8ToString(mime_type, mime_type_length);
+            const url = URL.createObjectURL(new Blob([bytes], { type: type }));
+            const link = document.createElement("a");
+            link.href = url;
+            link.download = UTF8ToString(name, name_length);
+            // some browsers only follow links that are in the document
+            document.body.appendChild(link);
+            link.click();
+            link.remove();
+            // revoking right away can cancel the download before it starts
+            setTimeout(function () { URL.revokeObjectURL(url); }, 1000);
+        };
+    },
+    name: "anniversary",
+    version: "0.1.0",
+});